@@ -0,0 +1,157 @@
+//! Derive macros for [`message_db`](https://docs.rs/message_db).
+//!
+//! See [`Message`].
+
+#![warn(missing_docs)]
+
+use heck::ToUpperCamelCase;
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Lit, Meta, NestedMeta, Result};
+
+/// Implements `MessageType` for a struct.
+///
+/// The message type defaults to the name of the struct, which can be a unit,
+/// tuple or named field struct. Message type names must follow Eventide's
+/// PascalCase naming convention, starting with an uppercase letter and
+/// containing only letters and digits, and a compile error is emitted if they
+/// do not.
+///
+/// # Attributes
+///
+/// - `#[message(rename = "...")]` overrides the message type name.
+/// - `#[message(schema_version = "...")]` sets the schema version written to
+///   the message's metadata.
+///
+/// # Example
+///
+/// ```
+/// use message_db::message::MessageType;
+/// use message_db_derive::Message;
+/// use serde::Serialize;
+///
+/// #[derive(Message, Serialize)]
+/// #[message(rename = "AccountOpened", schema_version = 2)]
+/// struct OpenedAccount {
+///     initial_balance: i64,
+/// }
+///
+/// assert_eq!(OpenedAccount::MSG_TYPE, "AccountOpened");
+/// assert_eq!(OpenedAccount::SCHEMA_VERSION, Some("2"));
+/// ```
+///
+/// Message type names which are not PascalCase are rejected.
+///
+/// ```compile_fail
+/// use message_db_derive::Message;
+///
+/// #[derive(Message)]
+/// #[message(rename = "account_opened")]
+/// struct AccountOpened;
+/// ```
+#[proc_macro_derive(Message, attributes(message))]
+pub fn derive_message(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_message(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct MessageAttrs {
+    rename: Option<(String, Span)>,
+    schema_version: Option<String>,
+}
+
+fn expand_message(input: DeriveInput) -> Result<proc_macro2::TokenStream> {
+    if !matches!(input.data, Data::Struct(_)) {
+        return Err(Error::new(
+            input.ident.span(),
+            "`Message` can only be derived for structs",
+        ));
+    }
+
+    let attrs = parse_message_attrs(&input)?;
+    let (msg_type, span) = attrs
+        .rename
+        .unwrap_or_else(|| (input.ident.to_string(), input.ident.span()));
+    if !is_pascal_case(&msg_type) {
+        return Err(Error::new(
+            span,
+            format!(
+                "message type `{msg_type}` must be PascalCase, eg `{}`",
+                msg_type.to_upper_camel_case()
+            ),
+        ));
+    }
+
+    let schema_version = match attrs.schema_version {
+        Some(schema_version) => quote!(::std::option::Option::Some(#schema_version)),
+        None => quote!(::std::option::Option::None),
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::message_db::message::MessageType for #ident #ty_generics #where_clause {
+            const MSG_TYPE: &'static str = #msg_type;
+            const SCHEMA_VERSION: ::std::option::Option<&'static str> = #schema_version;
+        }
+    })
+}
+
+/// Returns `true` if the name starts with an uppercase letter, and contains
+/// only letters and digits.
+///
+/// Acronyms are allowed, as in `HTTPRequestReceived`.
+fn is_pascal_case(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(char::is_uppercase) && chars.all(char::is_alphanumeric)
+}
+
+fn parse_message_attrs(input: &DeriveInput) -> Result<MessageAttrs> {
+    let mut attrs = MessageAttrs::default();
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("message"))
+    {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(Error::new(meta.span(), "expected `#[message(...)]`")),
+        };
+
+        for nested in list.nested {
+            let name_value = match nested {
+                NestedMeta::Meta(Meta::NameValue(name_value)) => name_value,
+                nested => return Err(Error::new(nested.span(), "expected `key = value`")),
+            };
+
+            if name_value.path.is_ident("rename") {
+                match &name_value.lit {
+                    Lit::Str(lit) => attrs.rename = Some((lit.value(), lit.span())),
+                    lit => return Err(Error::new(lit.span(), "expected string literal")),
+                }
+            } else if name_value.path.is_ident("schema_version") {
+                match &name_value.lit {
+                    Lit::Str(lit) => attrs.schema_version = Some(lit.value()),
+                    Lit::Int(lit) => attrs.schema_version = Some(lit.base10_digits().to_string()),
+                    lit => {
+                        return Err(Error::new(lit.span(), "expected string or integer literal"))
+                    }
+                }
+            } else {
+                return Err(Error::new(
+                    name_value.path.span(),
+                    "unknown message attribute, expected `rename` or `schema_version`",
+                ));
+            }
+        }
+    }
+
+    Ok(attrs)
+}
@@ -0,0 +1,23 @@
+use message_db_derive::Message;
+
+#[derive(Message)]
+#[message = "AccountOpened"]
+struct AccountOpened;
+
+#[derive(Message)]
+#[message(rename)]
+struct Deposited;
+
+#[derive(Message)]
+#[message(rename = 1)]
+struct Withdrawn;
+
+#[derive(Message)]
+#[message(schema_version = true)]
+struct Closed;
+
+#[derive(Message)]
+#[message(version = "1")]
+struct Reopened;
+
+fn main() {}
@@ -0,0 +1,8 @@
+use message_db_derive::Message;
+
+#[derive(Message)]
+enum AccountEvent {
+    Opened,
+}
+
+fn main() {}
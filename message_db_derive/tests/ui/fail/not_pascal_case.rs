@@ -0,0 +1,15 @@
+use message_db_derive::Message;
+
+#[derive(Message)]
+#[message(rename = "account_opened")]
+struct AccountOpened;
+
+#[derive(Message)]
+#[message(rename = "accountOpened")]
+struct Opened;
+
+#[derive(Message)]
+#[message(rename = "Account Opened")]
+struct Created;
+
+fn main() {}
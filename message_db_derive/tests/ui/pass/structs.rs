@@ -0,0 +1,29 @@
+use message_db::message::MessageType;
+use message_db_derive::Message;
+
+#[derive(Message)]
+struct AccountOpened {
+    _initial_balance: i64,
+}
+
+#[derive(Message)]
+struct AccountClosed;
+
+#[derive(Message)]
+struct Deposited(i64);
+
+#[derive(Message)]
+struct HTTPRequestReceived;
+
+#[derive(Message)]
+#[message(rename = "Withdrawn2", schema_version = "2")]
+struct Withdrawn;
+
+fn main() {
+    assert_eq!(AccountOpened::MSG_TYPE, "AccountOpened");
+    assert_eq!(AccountClosed::MSG_TYPE, "AccountClosed");
+    assert_eq!(Deposited::MSG_TYPE, "Deposited");
+    assert_eq!(HTTPRequestReceived::MSG_TYPE, "HTTPRequestReceived");
+    assert_eq!(Withdrawn::MSG_TYPE, "Withdrawn2");
+    assert_eq!(Withdrawn::SCHEMA_VERSION, Some("2"));
+}
@@ -2,10 +2,32 @@
 //!
 //! See [`MessageStore`].
 
+mod causation;
 mod client;
+mod condition;
 mod consumer;
+mod error;
+mod executor;
+mod expected_version;
+mod install;
 mod message;
+mod position;
+mod read;
+mod retry;
+mod settings;
 mod stream_name;
+mod unit_of_work;
+mod version;
 
+pub use causation::*;
 pub use client::*;
+pub use condition::*;
 pub use consumer::*;
+pub use executor::*;
+pub use expected_version::*;
+pub use install::*;
+pub use read::*;
+pub use retry::*;
+pub use settings::*;
+pub use unit_of_work::*;
+pub use version::*;
@@ -0,0 +1,562 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, PgConnection};
+use tracing::warn;
+use typed_builder::TypedBuilder;
+
+use crate::database::client::{quote_ident, MessageStore, MESSAGE_COLUMNS};
+use crate::database::executor::MessageStoreExecutor;
+use crate::database::read::DEFAULT_BATCH_SIZE;
+use crate::message::{
+    message_identifier, DeserializeMessage, GenericMessage, GlobalPosition, Message, StreamPosition,
+};
+use crate::stream_name::StreamName;
+use crate::{Error, Result};
+
+/// Returns the indexes supporting [`MessageStore::get_caused_messages`] and
+/// [`MessageStore::get_correlated_messages`].
+///
+/// Metadata written by this crate uses snake case keys, unlike the
+/// `correlationStreamName` key indexed by Message DB.
+///
+/// Each index is a name and the indexed columns, which refer to functions in
+/// the quoted schema.
+fn metadata_indexes(schema: &str) -> [(&'static str, String); 3] {
+    [
+        (
+            "messages_causation",
+            "(metadata->>'causation_message_stream_name'), (metadata->>'causation_message_position')"
+                .to_string(),
+        ),
+        (
+            "messages_correlation_stream",
+            "(metadata->>'correlation_stream_name'), global_position".to_string(),
+        ),
+        (
+            "messages_correlation_category",
+            format!("{schema}.category(metadata->>'correlation_stream_name'), global_position"),
+        ),
+    ]
+}
+
+/// Interval between attempts to acquire the lock serializing
+/// [`MessageStore::create_metadata_indexes`].
+const METADATA_INDEXES_LOCK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A tree of messages linked by their causation metadata, as built by
+/// [`MessageStore::causation_tree`].
+///
+/// Nodes refer to each other by their index in `nodes`, so that large trees
+/// can be traversed and serialized without recursion.
+///
+/// The tree can be exported as JSON, or as a Graphviz DOT or Mermaid diagram.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CausationTree {
+    /// Messages in the tree, breadth first from the first message in the
+    /// workflow.
+    pub nodes: Vec<CausationNode>,
+    /// Whether messages were left out of the tree by [`CausationTreeOpts`].
+    pub truncated: bool,
+}
+
+/// A message and the messages it caused.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CausationNode {
+    /// The message.
+    pub message: GenericMessage,
+    /// Index of the message which caused the message, or `None` for the root.
+    pub caused_by: Option<usize>,
+    /// Indexes of the messages caused by the message, in global position order.
+    pub caused: Vec<usize>,
+}
+
+/// Options for [`MessageStore::causation_tree`].
+#[derive(Clone, Debug, PartialEq, Eq, TypedBuilder)]
+pub struct CausationTreeOpts {
+    /// Maximum number of messages in the tree.
+    #[builder(default = 1000)]
+    max_nodes: usize,
+    /// Maximum number of causation links followed back to the root, and from
+    /// the root to the messages it caused.
+    #[builder(default = 100)]
+    max_depth: usize,
+}
+
+/// Options for [`MessageStore::get_correlated_messages`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, TypedBuilder)]
+pub struct GetCorrelatedMessagesOpts {
+    #[builder(default, setter(strip_option))]
+    position: Option<GlobalPosition>,
+    #[builder(default, setter(strip_option))]
+    batch_size: Option<i64>,
+}
+
+impl MessageStore {
+    /// Retrieves the messages caused by the message at the position in the
+    /// stream, in global position order, from every category.
+    ///
+    /// These are the messages whose metadata `causation_message_stream_name`
+    /// and `causation_message_position` refer to the message, as set by
+    /// [`Metadata::follow`](crate::message::Metadata::follow).
+    ///
+    /// See [`MessageStore::create_metadata_indexes`].
+    pub async fn get_caused_messages<'e, 'c: 'e, T, E>(
+        executor: E,
+        stream_name: &str,
+        position: StreamPosition,
+    ) -> Result<Vec<Message<T>>>
+    where
+        T: for<'de> Deserialize<'de>,
+        E: 'e + MessageStoreExecutor<'c>,
+    {
+        let schema = quote_ident(executor.schema());
+        let messages: Vec<GenericMessage> = sqlx::query_as(&format!(
+            r#"
+                SELECT {MESSAGE_COLUMNS}
+                FROM {schema}.messages
+                WHERE metadata->>'causation_message_stream_name' = $1
+                    AND metadata->>'causation_message_position' = $2
+                ORDER BY global_position
+            "#
+        ))
+        .bind(stream_name)
+        .bind(position.to_string())
+        .fetch_all(executor)
+        .await?;
+
+        messages.deserialize_messages()
+    }
+
+    /// Retrieves messages from every category correlated with a stream, in
+    /// global position order, optionally specifying the starting global
+    /// position and the number of messages to retrieve.
+    ///
+    /// If `correlation` is a category, messages correlated with any stream in
+    /// the category are retrieved, as with
+    /// [`Metadata::is_correlated`](crate::message::Metadata::is_correlated).
+    ///
+    /// See [`MessageStore::create_metadata_indexes`].
+    pub async fn get_correlated_messages<'e, 'c: 'e, T, E>(
+        executor: E,
+        correlation: &str,
+        opts: &GetCorrelatedMessagesOpts,
+    ) -> Result<Vec<Message<T>>>
+    where
+        T: for<'de> Deserialize<'de>,
+        E: 'e + MessageStoreExecutor<'c>,
+    {
+        let schema = quote_ident(executor.schema());
+        let correlation_stream_name = if correlation.contains(StreamName::ID_SEPARATOR) {
+            "(metadata->>'correlation_stream_name')".to_string()
+        } else {
+            format!("{schema}.category(metadata->>'correlation_stream_name')")
+        };
+        let messages: Vec<GenericMessage> = sqlx::query_as(&format!(
+            r#"
+                SELECT {MESSAGE_COLUMNS}
+                FROM {schema}.messages
+                WHERE {correlation_stream_name} = $1 AND global_position >= $2
+                ORDER BY global_position
+                LIMIT $3
+            "#
+        ))
+        .bind(correlation)
+        .bind(opts.position.unwrap_or_default())
+        .bind(opts.batch_size.unwrap_or(DEFAULT_BATCH_SIZE))
+        .fetch_all(executor)
+        .await?;
+
+        messages.deserialize_messages()
+    }
+
+    /// Builds the tree of messages in the message's workflow.
+    ///
+    /// The message's causation metadata is followed back to the first message
+    /// in the workflow, and every message caused by it, directly or
+    /// indirectly, is retrieved. If a causation message no longer exists, the
+    /// earliest message found is used as the root.
+    ///
+    /// Each direction is retrieved with a single recursive query, limited by
+    /// [`CausationTreeOpts`]. If a limit is reached, the tree is marked as
+    /// [truncated](CausationTree::truncated).
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use message_db::database::{CausationTreeOpts, MessageStore};
+    ///
+    /// let tree =
+    ///     MessageStore::causation_tree(&message_store, &message, &CausationTreeOpts::default())
+    ///         .await?;
+    /// std::fs::write("workflow.dot", tree.to_dot())?;
+    /// ```
+    pub async fn causation_tree<'e, 'c: 'e, T, E>(
+        executor: E,
+        message: &Message<T>,
+        opts: &CausationTreeOpts,
+    ) -> Result<CausationTree>
+    where
+        E: 'e + MessageStoreExecutor<'c> + Clone,
+    {
+        let schema = quote_ident(executor.schema());
+        // Ancestors are ordered from the message back to the root.
+        let ancestors: Vec<GenericMessage> = sqlx::query_as(&format!(
+            r#"
+                WITH RECURSIVE ancestors AS (
+                    SELECT messages.*, 0 AS depth
+                    FROM {schema}.messages
+                    WHERE stream_name = $1 AND position = $2
+                  UNION ALL
+                    SELECT messages.*, ancestors.depth + 1
+                    FROM ancestors
+                    JOIN {schema}.messages
+                        ON messages.stream_name = ancestors.metadata->>'causation_message_stream_name'
+                        AND messages.position::varchar = ancestors.metadata->>'causation_message_position'
+                    WHERE ancestors.depth < $3
+                )
+                SELECT {MESSAGE_COLUMNS}
+                FROM ancestors
+                ORDER BY depth
+            "#
+        ))
+        .bind(&message.stream_name)
+        .bind(message.position)
+        .bind(i64::try_from(opts.max_depth).unwrap_or(i64::MAX))
+        .fetch_all(executor.clone())
+        .await?;
+
+        let mut visited = HashSet::new();
+        let mut root = None;
+        for ancestor in ancestors {
+            // Guards against metadata which has been written in a cycle.
+            if !visited.insert(message_identifier(&ancestor.stream_name, ancestor.position)) {
+                break;
+            }
+            root = Some(ancestor);
+        }
+        let Some(root) = root else {
+            return Err(Error::MessageNotFound {
+                stream_name: message.stream_name.clone(),
+                position: message.position,
+            });
+        };
+        let mut truncated = visited.len() > opts.max_depth
+            && root.metadata.causation_message_identifier().is_some();
+
+        // Descendants are retrieved one level deeper than the maximum depth, to
+        // detect truncation. Recursive queries are evaluated breadth first, so
+        // the limit stops the recursion once enough messages are found.
+        let descendants: Vec<GenericMessage> = sqlx::query_as(&format!(
+            r#"
+                WITH RECURSIVE descendants AS (
+                    SELECT messages.*, 0 AS depth
+                    FROM {schema}.messages
+                    WHERE stream_name = $1 AND position = $2
+                  UNION ALL
+                    SELECT messages.*, descendants.depth + 1
+                    FROM descendants
+                    JOIN {schema}.messages
+                        ON messages.metadata->>'causation_message_stream_name' = descendants.stream_name
+                        AND messages.metadata->>'causation_message_position' = descendants.position::varchar
+                    WHERE descendants.depth <= $3
+                )
+                SELECT {MESSAGE_COLUMNS}
+                FROM (SELECT * FROM descendants LIMIT $4) AS descendants
+                ORDER BY depth, global_position
+            "#
+        ))
+        .bind(&root.stream_name)
+        .bind(root.position)
+        .bind(i64::try_from(opts.max_depth).unwrap_or(i64::MAX))
+        .bind(i64::try_from(opts.max_nodes.saturating_add(1)).unwrap_or(i64::MAX))
+        .fetch_all(executor)
+        .await?;
+
+        let mut nodes: Vec<CausationNode> = Vec::new();
+        let mut depths: Vec<usize> = Vec::new();
+        let mut indexes: HashMap<String, usize> = HashMap::new();
+        for message in descendants {
+            let identifier = message_identifier(&message.stream_name, message.position);
+            if indexes.contains_key(&identifier) {
+                continue;
+            }
+            // Every message but the root is caused by a message already in the
+            // tree, as they are ordered by depth.
+            let caused_by = if nodes.is_empty() {
+                None
+            } else {
+                let causation = message.metadata.causation_message_identifier();
+                match causation.and_then(|causation| indexes.get(&causation)) {
+                    Some(index) => Some(*index),
+                    None => continue,
+                }
+            };
+            let depth = caused_by.map_or(0, |index| depths[index] + 1);
+            if depth > opts.max_depth || nodes.len() == opts.max_nodes {
+                truncated = true;
+                continue;
+            }
+
+            let index = nodes.len();
+            if let Some(caused_by) = caused_by {
+                nodes[caused_by].caused.push(index);
+            }
+            indexes.insert(identifier, index);
+            depths.push(depth);
+            nodes.push(CausationNode {
+                message,
+                caused_by,
+                caused: Vec::new(),
+            });
+        }
+
+        Ok(CausationTree { nodes, truncated })
+    }
+
+    /// Creates the indexes used to query messages by causation and
+    /// correlation metadata, if they do not exist.
+    ///
+    /// The indexes are built concurrently, so that writes are not blocked
+    /// while indexing an existing message store. A concurrent build which
+    /// failed leaves an invalid index, which is not used by queries, so
+    /// invalid indexes are dropped and built again.
+    ///
+    /// An index is also invalid while it is being built, so concurrent calls
+    /// are serialized with an advisory lock, rather than dropping each other's
+    /// builds.
+    pub async fn create_metadata_indexes(&self) -> Result<()> {
+        // The lock is held by the session, so the connection is detached from
+        // the pool, and closed rather than reused if the build is cancelled.
+        let mut conn = self.pool().acquire().await?.detach();
+        // Waiting for the lock in a statement would hold a transaction open,
+        // which the concurrent build waits for in turn, so it is polled.
+        while !sqlx::query_scalar::<_, bool>(
+            "SELECT pg_try_advisory_lock(hashtext('message_store.metadata_indexes'))",
+        )
+        .fetch_one(&mut conn)
+        .await?
+        {
+            tokio::time::sleep(METADATA_INDEXES_LOCK_POLL_INTERVAL).await;
+        }
+        let result = create_metadata_indexes(&mut conn, self.schema()).await;
+        sqlx::query("SELECT pg_advisory_unlock(hashtext('message_store.metadata_indexes'))")
+            .execute(&mut conn)
+            .await?;
+
+        result
+    }
+}
+
+async fn create_metadata_indexes(conn: &mut PgConnection, schema: &str) -> Result<()> {
+    let schema = quote_ident(schema);
+    for (name, columns) in metadata_indexes(&schema) {
+        let valid: Option<bool> = sqlx::query_scalar(
+            "SELECT indisvalid FROM pg_index WHERE indexrelid = to_regclass($1)",
+        )
+        .bind(format!("{schema}.{name}"))
+        .fetch_optional(&mut *conn)
+        .await?;
+        if valid == Some(false) {
+            warn!(index = %name, "rebuilding invalid index");
+            conn.execute(format!("DROP INDEX CONCURRENTLY IF EXISTS {schema}.{name}").as_str())
+                .await?;
+        }
+
+        conn.execute(
+            format!(
+                "CREATE INDEX CONCURRENTLY IF NOT EXISTS {name} ON {schema}.messages ({columns})"
+            )
+            .as_str(),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+impl CausationTree {
+    /// Returns the first message in the workflow, or `None` if the tree is
+    /// empty, as when [`CausationTreeOpts`] allows no messages.
+    pub fn root(&self) -> Option<&CausationNode> {
+        self.nodes.first()
+    }
+
+    /// Returns the tree as pretty printed JSON.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(Error::SerializeData)
+    }
+
+    /// Returns the tree as a Graphviz DOT digraph.
+    pub fn to_dot(&self) -> String {
+        let mut dot = "digraph causation {\n".to_string();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let label = format!(
+                "{}\\n{}",
+                dot_escape(&node.message.msg_type),
+                dot_escape(&message_label(&node.message))
+            );
+            writeln!(dot, "    n{i} [label=\"{label}\"];").unwrap();
+        }
+        for (i, node) in self.nodes.iter().enumerate() {
+            if let Some(caused_by) = node.caused_by {
+                writeln!(dot, "    n{caused_by} -> n{i};").unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Returns the tree as a Mermaid flowchart.
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = "flowchart TD\n".to_string();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let label = format!(
+                "{}<br/>{}",
+                mermaid_escape(&node.message.msg_type),
+                mermaid_escape(&message_label(&node.message))
+            );
+            writeln!(mermaid, "    n{i}[\"{label}\"]").unwrap();
+        }
+        for (i, node) in self.nodes.iter().enumerate() {
+            if let Some(caused_by) = node.caused_by {
+                writeln!(mermaid, "    n{caused_by} --> n{i}").unwrap();
+            }
+        }
+        mermaid
+    }
+}
+
+impl Default for CausationTreeOpts {
+    fn default() -> Self {
+        CausationTreeOpts::builder().build()
+    }
+}
+
+fn message_label(message: &GenericMessage) -> String {
+    message_identifier(&message.stream_name, message.position)
+}
+
+/// Escapes a string in a quoted DOT label.
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Escapes a string in a quoted Mermaid label using entity codes.
+fn mermaid_escape(s: &str) -> String {
+    s.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use serde_json::Value;
+    use uuid::Uuid;
+
+    use super::{CausationNode, CausationTree};
+    use crate::message::{GenericMessage, GlobalPosition, Message, Metadata, StreamPosition};
+
+    fn message(stream_name: &str, msg_type: &str, position: i64) -> GenericMessage {
+        Message {
+            id: Uuid::nil(),
+            stream_name: stream_name.parse().unwrap(),
+            msg_type: msg_type.to_string(),
+            position: StreamPosition(position),
+            global_position: GlobalPosition(1),
+            data: Value::Null,
+            metadata: Metadata::default(),
+            time: Utc.timestamp_opt(0, 0).unwrap(),
+        }
+    }
+
+    fn tree() -> CausationTree {
+        CausationTree {
+            nodes: vec![
+                CausationNode {
+                    message: message("accountCommand-1", "Deposit", 0),
+                    caused_by: None,
+                    caused: vec![1, 2],
+                },
+                CausationNode {
+                    message: message("account-1", "Deposited", 3),
+                    caused_by: Some(0),
+                    caused: vec![],
+                },
+                CausationNode {
+                    message: message("audit-1", r#"Say "hi""#, 0),
+                    caused_by: Some(0),
+                    caused: vec![],
+                },
+            ],
+            truncated: false,
+        }
+    }
+
+    #[test]
+    fn it_renders_dot() {
+        assert_eq!(
+            tree().to_dot(),
+            r#"digraph causation {
+    n0 [label="Deposit\naccountCommand-1/0"];
+    n1 [label="Deposited\naccount-1/3"];
+    n2 [label="Say \"hi\"\naudit-1/0"];
+    n0 -> n1;
+    n0 -> n2;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn it_renders_mermaid() {
+        assert_eq!(
+            tree().to_mermaid(),
+            r#"flowchart TD
+    n0["Deposit<br/>accountCommand-1/0"]
+    n1["Deposited<br/>account-1/3"]
+    n2["Say #quot;hi#quot;<br/>audit-1/0"]
+    n0 --> n1
+    n0 --> n2
+"#
+        );
+    }
+
+    #[test]
+    fn it_exports_deep_trees() {
+        let depth: usize = 20_000;
+        let deposited = message("account-1", "Deposited", 0);
+        let nodes = (0..depth)
+            .map(|i| CausationNode {
+                message: deposited.clone(),
+                caused_by: i.checked_sub(1),
+                caused: if i + 1 < depth { vec![i + 1] } else { vec![] },
+            })
+            .collect();
+        let tree = CausationTree {
+            nodes,
+            truncated: false,
+        };
+
+        assert!(tree.to_json().is_ok());
+        assert_eq!(tree.to_dot().lines().count(), depth * 2 + 1);
+    }
+
+    #[test]
+    fn it_has_no_root_when_empty() {
+        assert_eq!(
+            tree().root().map(|root| root.message.msg_type.as_str()),
+            Some("Deposit")
+        );
+
+        let tree = CausationTree {
+            nodes: vec![],
+            truncated: true,
+        };
+        assert!(tree.root().is_none());
+        assert_eq!(tree.to_dot(), "digraph causation {\n}\n");
+    }
+}
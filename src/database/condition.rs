@@ -0,0 +1,236 @@
+use std::fmt::{self, Write};
+use std::ops::{Bound, RangeBounds};
+
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+
+use crate::message::{GlobalPosition, StreamPosition};
+
+/// A condition appended to the WHERE clause when retrieving messages.
+///
+/// Conditions render to SQL with all values quoted, and can be combined with
+/// [`Condition::and`] and [`Condition::or`].
+///
+/// Retrieving messages with a condition requires the
+/// [`Setting::SqlCondition`](crate::database::Setting::SqlCondition) setting.
+///
+/// # Example
+///
+/// ```
+/// use message_db::database::Condition;
+/// use message_db::message::StreamPosition;
+///
+/// let condition = Condition::msg_type_in(["Deposited", "Withdrawn"])
+///     .and(Condition::position(StreamPosition(10)..))
+///     .or(Condition::metadata_eq("reply_stream_name", "account:command-123"));
+///
+/// assert_eq!(
+///     condition.to_string(),
+///     r#"((type IN (E'Deposited', E'Withdrawn')) AND (position >= 10)) OR (metadata @> E'{"reply_stream_name":"account:command-123"}'::jsonb)"#,
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Condition {
+    sql: String,
+}
+
+impl Condition {
+    /// Matches messages with the message type.
+    pub fn msg_type(msg_type: &str) -> Self {
+        Condition {
+            sql: format!("type = {}", quote_literal(msg_type)),
+        }
+    }
+
+    /// Matches messages with any of the message types.
+    pub fn msg_type_in<I, S>(msg_types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut msg_types = msg_types.into_iter().peekable();
+        if msg_types.peek().is_none() {
+            return Condition {
+                sql: "FALSE".to_string(),
+            };
+        }
+
+        let mut sql = "type IN (".to_string();
+        for (i, msg_type) in msg_types.enumerate() {
+            if i > 0 {
+                sql.push_str(", ");
+            }
+            sql.push_str(&quote_literal(msg_type.as_ref()));
+        }
+        sql.push(')');
+
+        Condition { sql }
+    }
+
+    /// Matches messages whose metadata has a property equal to the value.
+    pub fn metadata_eq(property: &str, value: impl Into<Value>) -> Self {
+        let mut object = Map::new();
+        object.insert(property.to_string(), value.into());
+        Condition {
+            sql: format!(
+                "metadata @> {}::jsonb",
+                quote_literal(&Value::Object(object).to_string())
+            ),
+        }
+    }
+
+    /// Matches messages written within the time range.
+    pub fn time(range: impl RangeBounds<DateTime<Utc>>) -> Self {
+        // Message times are stored in UTC without a time zone, with microsecond
+        // precision.
+        Self::range("time", range, |time| {
+            format!(
+                "{}::timestamp",
+                quote_literal(&time.naive_utc().format("%Y-%m-%d %H:%M:%S%.6f").to_string())
+            )
+        })
+    }
+
+    /// Matches messages within the stream position range.
+    pub fn position(range: impl RangeBounds<StreamPosition>) -> Self {
+        Self::range("position", range, StreamPosition::to_string)
+    }
+
+    /// Matches messages within the global position range.
+    pub fn global_position(range: impl RangeBounds<GlobalPosition>) -> Self {
+        Self::range("global_position", range, GlobalPosition::to_string)
+    }
+
+    /// Matches messages matching both conditions.
+    pub fn and(self, other: Condition) -> Self {
+        Condition {
+            sql: format!("({}) AND ({})", self.sql, other.sql),
+        }
+    }
+
+    /// Matches messages matching either condition.
+    pub fn or(self, other: Condition) -> Self {
+        Condition {
+            sql: format!("({}) OR ({})", self.sql, other.sql),
+        }
+    }
+
+    /// Matches messages not matching the condition.
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Condition {
+            sql: format!("NOT ({})", self.sql),
+        }
+    }
+
+    /// Creates a condition from raw SQL.
+    ///
+    /// The SQL is used as is, and must not contain untrusted input.
+    pub fn raw(sql: impl Into<String>) -> Self {
+        Condition { sql: sql.into() }
+    }
+
+    /// Returns the condition as SQL.
+    pub fn as_sql(&self) -> &str {
+        &self.sql
+    }
+
+    fn range<T, R, F>(column: &str, range: R, render: F) -> Self
+    where
+        R: RangeBounds<T>,
+        F: Fn(&T) -> String,
+    {
+        let mut sql = String::new();
+        match range.start_bound() {
+            Bound::Included(start) => write!(sql, "{column} >= {}", render(start)).unwrap(),
+            Bound::Excluded(start) => write!(sql, "{column} > {}", render(start)).unwrap(),
+            Bound::Unbounded => {}
+        }
+
+        let end = match range.end_bound() {
+            Bound::Included(end) => Some(("<=", render(end))),
+            Bound::Excluded(end) => Some(("<", render(end))),
+            Bound::Unbounded => None,
+        };
+        if let Some((op, end)) = end {
+            if !sql.is_empty() {
+                sql.push_str(" AND ");
+            }
+            write!(sql, "{column} {op} {end}").unwrap();
+        }
+
+        if sql.is_empty() {
+            sql.push_str("TRUE");
+        }
+
+        Condition { sql }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.sql.fmt(f)
+    }
+}
+
+/// Quotes a string literal as an escape string, which is interpreted the same
+/// regardless of `standard_conforming_strings`.
+fn quote_literal(s: &str) -> String {
+    format!("E'{}'", s.replace('\\', "\\\\").replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    use super::Condition;
+    use crate::message::{GlobalPosition, StreamPosition};
+
+    #[test]
+    fn it_quotes_values() {
+        assert_eq!(
+            Condition::msg_type("Deposited'; DROP TABLE messages; --").as_sql(),
+            r"type = E'Deposited''; DROP TABLE messages; --'"
+        );
+        assert_eq!(Condition::msg_type(r"a\'b").as_sql(), r"type = E'a\\''b'");
+        assert_eq!(
+            Condition::metadata_eq("schema_version", json!("it's")).as_sql(),
+            r#"metadata @> E'{"schema_version":"it''s"}'::jsonb"#
+        );
+        assert_eq!(
+            Condition::msg_type_in(Vec::<String>::new()).as_sql(),
+            "FALSE"
+        );
+    }
+
+    #[test]
+    fn it_renders_ranges() {
+        assert_eq!(
+            Condition::position(StreamPosition(2)..StreamPosition(5)).as_sql(),
+            "position >= 2 AND position < 5"
+        );
+        assert_eq!(
+            Condition::global_position(..=GlobalPosition(10)).as_sql(),
+            "global_position <= 10"
+        );
+        assert_eq!(Condition::position(..).as_sql(), "TRUE");
+
+        let start = Utc.with_ymd_and_hms(2022, 12, 1, 9, 30, 0).unwrap();
+        assert_eq!(
+            Condition::time(start..).as_sql(),
+            "time >= E'2022-12-01 09:30:00.000000'::timestamp"
+        );
+    }
+
+    #[test]
+    fn it_combines_conditions() {
+        let condition = Condition::msg_type("Deposited")
+            .or(Condition::msg_type("Withdrawn"))
+            .and(Condition::position(StreamPosition(3)..).not());
+        assert_eq!(
+            condition.as_sql(),
+            "((type = E'Deposited') OR (type = E'Withdrawn')) AND (NOT (position >= 3))"
+        );
+    }
+}
@@ -1,5 +1,7 @@
+use std::collections::VecDeque;
 use std::marker::PhantomData;
 use std::pin::Pin;
+use std::slice;
 use std::str::FromStr;
 use std::task::{Context, Poll};
 use std::time::Duration;
@@ -9,14 +11,18 @@ use futures::stream::SelectAll;
 use futures::{ready, FutureExt, Stream};
 use pin_project::pin_project;
 use serde::{Deserialize, Serialize};
-use sqlx::{Executor, Postgres};
 use tokio::time::Instant;
 use tokio_util::sync::ReusableBoxFuture;
 use tracing::{error, info};
 use typed_builder::TypedBuilder;
 
 use crate::database::client::{GetCategoryMessagesOpts, MessageStore, WriteMessageOpts};
-use crate::message::{DeserializeMessage, GenericMessage, Message};
+use crate::database::executor::MessageStoreExecutor;
+use crate::database::{Condition, ExpectedVersion};
+use crate::message::{
+    DeserializeEach, DeserializeMessage, GenericMessage, GlobalPosition, Message, StreamPosition,
+    UnknownTypePolicy,
+};
 use crate::stream_name::{Category, StreamName, ID};
 use crate::Result;
 
@@ -39,14 +45,14 @@ pub struct SubscribeToCategoryOpts<'a> {
     #[builder(default, setter(strip_option))]
     group_size: Option<i64>,
     #[builder(default, setter(strip_option))]
-    condition: Option<&'a str>,
+    condition: Option<&'a Condition>,
 }
 
 #[derive(
     Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
 )]
 struct Recorded {
-    position: i64,
+    position: GlobalPosition,
 }
 
 impl MessageStore {
@@ -76,7 +82,7 @@ impl MessageStore {
     ) -> Result<SelectAll<CategoryStream<'a, E, T>>>
     where
         T: for<'de> Deserialize<'de> + 'a,
-        E: 'a + 'c + 'e + Executor<'c, Database = Postgres> + Clone + Send + Sync,
+        E: 'a + 'c + 'e + MessageStoreExecutor<'c> + Clone + Send + Sync,
     {
         let streams = futures::future::join_all(category_names.iter().map(|category_name| {
             Self::subscribe_to_category::<T, E>(executor.clone(), category_name, opts).boxed()
@@ -92,6 +98,12 @@ impl MessageStore {
     /// The consumer position is saved every
     /// `SubscribeToCategoryOpts::position_update_interval` messages consumed.
     ///
+    /// Consumer group options require [`Feature::ConsumerGroups`], otherwise
+    /// the stream returns [`Error::UnsupportedFeature`].
+    ///
+    /// [`Feature::ConsumerGroups`]: crate::database::Feature::ConsumerGroups
+    /// [`Error::UnsupportedFeature`]: crate::Error::UnsupportedFeature
+    ///
     /// # Example
     ///
     /// ```ignore
@@ -119,29 +131,26 @@ impl MessageStore {
     ) -> Result<CategoryStream<'a, E, T>>
     where
         T: for<'de> Deserialize<'de> + 'a,
-        E: 'a + 'c + 'e + Executor<'c, Database = Postgres> + Clone,
+        E: 'a + 'c + 'e + MessageStoreExecutor<'c> + Clone,
     {
-        let stream_name =
-            Self::position_stream_name(category_name.parse()?, opts.identifier)?.to_string();
-        let last_message = Self::get_last_stream_message::<Recorded, _>(
+        let stream_name = Self::position_stream_name(category_name.parse()?, opts.identifier)?;
+        // Filtering `get_last_stream_message` by type requires a newer server
+        // version than the messages table query.
+        let last_message = Self::get_last_stream_messages::<Recorded, _>(
             executor.clone(),
-            &stream_name,
+            slice::from_ref(&stream_name),
             Some("position"),
         )
-        .await?;
-        let expected_version = last_message
-            .as_ref()
-            .map(|last| last.position)
-            .unwrap_or(-1);
-        let last_position = last_message
-            .map(|recorded| recorded.position + 1)
-            .unwrap_or(-1);
+        .await?
+        .remove(&stream_name);
+        let position_version = last_message.as_ref().map(|last| last.position);
+        let last_position = last_message.map(|recorded| recorded.data.position.next());
 
         let fut = ReusableBoxFuture::new(make_future(
             executor.clone(),
             category_name,
             GetCategoryMessagesOpts {
-                position: Some(last_position),
+                position: last_position,
                 batch_size: opts.batch_size,
                 correlation: opts.correlation,
                 consumer_group_member: opts.group_member,
@@ -161,8 +170,8 @@ impl MessageStore {
             messages_since_last_position_update: 0,
             // position store
             update_position_future: None,
-            consumer_stream_name: stream_name,
-            expected_position_version: expected_version,
+            consumer_stream_name: stream_name.to_string(),
+            position_version,
         })
     }
 
@@ -174,11 +183,11 @@ impl MessageStore {
         executor: E,
         category_name: &str,
         identifier: Option<&str>,
-        position: i64,
+        position: GlobalPosition,
         opts: &WriteMessageOpts<'_>,
-    ) -> Result<i64>
+    ) -> Result<StreamPosition>
     where
-        E: 'e + Executor<'c, Database = Postgres>,
+        E: 'e + MessageStoreExecutor<'c>,
     {
         let stream_name =
             Self::position_stream_name(category_name.parse()?, identifier)?.to_string();
@@ -189,11 +198,11 @@ impl MessageStore {
     pub async fn write_consumer_position_to_stream<'e, 'c: 'e, E>(
         executor: E,
         stream_name: &str,
-        position: i64,
+        position: GlobalPosition,
         opts: &WriteMessageOpts<'_>,
-    ) -> Result<i64>
+    ) -> Result<StreamPosition>
     where
-        E: 'e + Executor<'c, Database = Postgres>,
+        E: 'e + MessageStoreExecutor<'c>,
     {
         let data = Recorded { position };
         Self::write_message(
@@ -233,19 +242,61 @@ pub struct CategoryStream<'a, E, T> {
     position_update_interval: usize,
     messages_since_last_position_update: usize,
     // position store
-    update_position_future: Option<BoxFuture<'a, Result<i64>>>,
+    update_position_future: Option<BoxFuture<'a, Result<GlobalPosition>>>,
     consumer_stream_name: String,
-    expected_position_version: i64,
+    position_version: Option<StreamPosition>,
+}
+
+impl<'a, E, T> CategoryStream<'a, E, T> {
+    /// Returns a stream of each message consumed, rather than of batches.
+    ///
+    /// Each message is deserialized on its own, so a message which cannot be
+    /// deserialized is returned as an error without failing the rest of its
+    /// batch. Messages of unknown types are handled according to
+    /// `unknown_types`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use futures::StreamExt;
+    /// use message_db::database::{MessageStore, SubscribeToCategoryOpts};
+    /// use message_db::message::UnknownTypePolicy;
+    ///
+    /// let mut stream = MessageStore::subscribe_to_category::<AccountEvent, _>(
+    ///     &message_store,
+    ///     "account",
+    ///     &SubscribeToCategoryOpts::default(),
+    /// )
+    /// .await?
+    /// .each(UnknownTypePolicy::Skip);
+    ///
+    /// while let Some(message) = stream.next().await {
+    ///     match message {
+    ///         Ok(message) => { /* ... */ }
+    ///         Err(err) => error!("{err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn each(self, unknown_types: UnknownTypePolicy) -> CategoryMessageStream<'a, E, T> {
+        CategoryMessageStream {
+            stream: self,
+            unknown_types,
+            messages: VecDeque::new(),
+        }
+    }
 }
 
-impl<'a, 'e, 'c: 'a + 'e, E, T> Stream for CategoryStream<'a, E, T>
+impl<'a, 'c: 'a, E, T> CategoryStream<'a, E, T>
 where
-    E: 'c + 'e + Executor<'c, Database = Postgres> + Clone,
-    T: for<'de> Deserialize<'de> + 'a,
+    E: 'c + MessageStoreExecutor<'c> + Clone,
+    T: 'a,
 {
-    type Item = Result<Vec<Message<T>>>;
-
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    /// Polls for the next batch of messages, saving the consumer position as
+    /// needed.
+    fn poll_batch(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Vec<GenericMessage>>>> {
         let this = self.project();
         let fut_poll = this.fut.poll(cx);
         let pos_fut_poll = this
@@ -256,7 +307,7 @@ where
         if let Some(pos_fut_poll) = pos_fut_poll {
             match pos_fut_poll {
                 Poll::Ready(Ok(pos)) => {
-                    info!(position = pos, "saved consumer position");
+                    info!(position = %pos, "saved consumer position");
                     *this.update_position_future = None;
                 }
                 Poll::Ready(Err(err)) => {
@@ -268,7 +319,7 @@ where
         }
         let (result, mut opts, poll_time) = ready!(fut_poll);
         if let Ok(Some(last)) = result.as_ref().map(|messages| messages.last()) {
-            opts.position = Some(last.global_position + 1);
+            opts.position = Some(last.global_position.next());
         }
 
         let sleep_duration = this.poll_interval.saturating_sub(poll_time.elapsed());
@@ -293,25 +344,73 @@ where
                             this.message_store.clone(),
                             this.consumer_stream_name.clone(),
                             pos,
-                            *this.expected_position_version,
+                            ExpectedVersion::from(*this.position_version),
                         )
                         .boxed(),
                     );
-                    *this.expected_position_version += 1;
+                    *this.position_version = Some(
+                        this.position_version
+                            .map_or(StreamPosition(0), StreamPosition::next),
+                    );
                     *this.messages_since_last_position_update = 0;
                 }
 
-                let messages: Result<Vec<_>, _> = messages.deserialize_messages();
-                match messages {
-                    Ok(messages) => Poll::Ready(Some(Ok(messages))),
-                    Err(err) => Poll::Ready(Some(Err(err))),
-                }
+                Poll::Ready(Some(Ok(messages)))
             }
             Err(err) => Poll::Ready(Some(Err(err))),
         }
     }
 }
 
+impl<'a, 'c: 'a, E, T> Stream for CategoryStream<'a, E, T>
+where
+    E: 'c + MessageStoreExecutor<'c> + Clone,
+    T: for<'de> Deserialize<'de> + 'a,
+{
+    type Item = Result<Vec<Message<T>>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_batch(cx)
+            .map(|batch| batch.map(|messages| messages?.deserialize_messages()))
+    }
+}
+
+/// A category stream of each message consumed.
+///
+/// This is returned by [`CategoryStream::each`].
+#[pin_project]
+pub struct CategoryMessageStream<'a, E, T> {
+    #[pin]
+    stream: CategoryStream<'a, E, T>,
+    unknown_types: UnknownTypePolicy,
+    messages: VecDeque<Result<Message<T>>>,
+}
+
+impl<'a, 'c: 'a, E, T> Stream for CategoryMessageStream<'a, E, T>
+where
+    E: 'c + MessageStoreExecutor<'c> + Clone,
+    T: for<'de> Deserialize<'de> + 'a,
+{
+    type Item = Result<Message<T>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            if let Some(message) = this.messages.pop_front() {
+                return Poll::Ready(Some(message));
+            }
+
+            match ready!(this.stream.as_mut().poll_batch(cx)) {
+                Some(Ok(messages)) => this
+                    .messages
+                    .extend(messages.deserialize_each(*this.unknown_types)),
+                Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
 async fn make_future<'a, 'b, 'c, 'e, 'f: 'e, T, E>(
     executor: E,
     category_name: &'b str,
@@ -324,7 +423,7 @@ async fn make_future<'a, 'b, 'c, 'e, 'f: 'e, T, E>(
 )
 where
     T: for<'de> Deserialize<'de> + 'a,
-    E: 'f + Executor<'f, Database = Postgres>,
+    E: 'f + MessageStoreExecutor<'f>,
 {
     if !sleep.is_zero() {
         tokio::time::sleep(sleep).await;
@@ -337,11 +436,11 @@ where
 async fn make_update_position_future<'e, 'c: 'e, E>(
     executor: E,
     stream_name: String,
-    pos: i64,
-    expected_version: i64,
-) -> Result<i64>
+    pos: GlobalPosition,
+    expected_version: ExpectedVersion,
+) -> Result<GlobalPosition>
 where
-    E: 'e + Executor<'c, Database = Postgres>,
+    E: 'e + MessageStoreExecutor<'c>,
 {
     MessageStore::write_consumer_position_to_stream(
         executor,
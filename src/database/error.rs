@@ -0,0 +1,167 @@
+use sqlx::postgres::PgDatabaseError;
+
+use crate::database::{ExpectedVersion, Feature};
+use crate::Error;
+
+/// Postgres error code raised by `RAISE EXCEPTION` in server functions.
+const RAISE_EXCEPTION: &str = "P0001";
+
+/// Exception raised when retrieving messages with a condition while
+/// `message_store.sql_condition` is off.
+const CONDITION_NOT_ACTIVATED: &str = "Retrieval with SQL condition is not activated";
+
+/// Postgres error code raised when a unique constraint is violated.
+const UNIQUE_VIOLATION: &str = "23505";
+
+/// Unique index on the message id.
+const MESSAGES_ID_CONSTRAINT: &str = "messages_id";
+
+/// Postgres error code raised when no function matches the arguments.
+const UNDEFINED_FUNCTION: &str = "42883";
+
+impl From<sqlx::Error> for Error {
+    fn from(err: sqlx::Error) -> Self {
+        if let sqlx::Error::Database(db_err) = &err {
+            if db_err.code().as_deref() == Some(RAISE_EXCEPTION) {
+                if let Some(err) = parse_wrong_expected_version(db_err.message()) {
+                    return err;
+                }
+
+                if db_err.message() == CONDITION_NOT_ACTIVATED {
+                    return Error::ConditionNotActivated;
+                }
+            }
+
+            if db_err.code().as_deref() == Some(UNIQUE_VIOLATION)
+                && db_err.constraint() == Some(MESSAGES_ID_CONSTRAINT)
+            {
+                let detail = db_err
+                    .try_downcast_ref::<PgDatabaseError>()
+                    .and_then(PgDatabaseError::detail);
+                if let Some(err) = detail.and_then(parse_duplicate_message_id) {
+                    return err;
+                }
+            }
+        }
+
+        Error::Database(err)
+    }
+}
+
+/// Converts an error calling a server function which uses `feature`.
+///
+/// Older server versions lack the parameters added for a feature, so the call
+/// fails with an undefined function error, which is returned as
+/// [`Error::UnsupportedFeature`].
+pub(crate) fn feature_error(err: sqlx::Error, feature: Option<Feature>) -> Error {
+    let code = err.as_database_error().and_then(|db_err| db_err.code());
+    match unsupported_feature(code.as_deref(), feature) {
+        Some(err) => err,
+        None => Error::from(err),
+    }
+}
+
+fn unsupported_feature(code: Option<&str>, feature: Option<Feature>) -> Option<Error> {
+    match (code, feature) {
+        (Some(UNDEFINED_FUNCTION), Some(feature)) => Some(Error::UnsupportedFeature { feature }),
+        _ => None,
+    }
+}
+
+/// Parses the exception raised by `write_message`.
+///
+/// `Wrong expected version: 2 (Stream: account-123, Stream Version: 3)`
+fn parse_wrong_expected_version(message: &str) -> Option<Error> {
+    let rest = message.strip_prefix("Wrong expected version: ")?;
+    let (expected, rest) = rest.split_once(" (Stream: ")?;
+    let (stream_name, actual) = rest.strip_suffix(')')?.rsplit_once(", Stream Version: ")?;
+
+    let actual = ExpectedVersion::from_sql(actual.parse().ok()?);
+    Some(Error::WrongExpectedVersion {
+        stream_name: stream_name.parse().ok()?,
+        expected: ExpectedVersion::from_sql(expected.parse().ok()?),
+        actual: match actual {
+            ExpectedVersion::Exact(position) => Some(position),
+            _ => None,
+        },
+    })
+}
+
+/// Parses the detail of a unique violation of the message id.
+///
+/// `Key (id)=(509db8d1-2e78-4c71-b9c6-f5452d83f396) already exists.`
+fn parse_duplicate_message_id(detail: &str) -> Option<Error> {
+    let id = detail
+        .strip_prefix("Key (id)=(")?
+        .strip_suffix(") already exists.")?;
+
+    Some(Error::DuplicateMessageId {
+        id: id.parse().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_duplicate_message_id, parse_wrong_expected_version, unsupported_feature};
+    use crate::database::{ExpectedVersion, Feature};
+    use crate::message::StreamPosition;
+    use crate::Error;
+
+    #[test]
+    fn it_parses_wrong_expected_version() {
+        let err = parse_wrong_expected_version(
+            "Wrong expected version: 2 (Stream: account-123, Stream Version: 3)",
+        );
+        assert!(matches!(
+            err,
+            Some(Error::WrongExpectedVersion {
+                stream_name,
+                expected: ExpectedVersion::Exact(StreamPosition(2)),
+                actual: Some(StreamPosition(3)),
+            }) if stream_name.to_string() == "account-123"
+        ));
+
+        let err = parse_wrong_expected_version(
+            "Wrong expected version: 0 (Stream: account-123, Stream Version: -1)",
+        );
+        assert!(matches!(
+            err,
+            Some(Error::WrongExpectedVersion {
+                expected: ExpectedVersion::Exact(StreamPosition(0)),
+                actual: None,
+                ..
+            })
+        ));
+
+        assert!(parse_wrong_expected_version("Must be a stream name: account").is_none());
+    }
+
+    #[test]
+    fn it_parses_duplicate_message_id() {
+        let err = parse_duplicate_message_id(
+            "Key (id)=(509db8d1-2e78-4c71-b9c6-f5452d83f396) already exists.",
+        );
+        assert!(matches!(
+            err,
+            Some(Error::DuplicateMessageId { id })
+                if id.to_string() == "509db8d1-2e78-4c71-b9c6-f5452d83f396"
+        ));
+
+        assert!(parse_duplicate_message_id("Key (id)=(abc) already exists.").is_none());
+    }
+
+    #[test]
+    fn it_converts_undefined_function_to_unsupported_feature() {
+        let err = unsupported_feature(Some("42883"), Some(Feature::ConsumerGroups));
+        assert!(matches!(
+            err,
+            Some(Error::UnsupportedFeature {
+                feature: Feature::ConsumerGroups
+            })
+        ));
+
+        assert!(unsupported_feature(Some("42883"), None).is_none());
+        assert!(unsupported_feature(Some("P0001"), Some(Feature::ConsumerGroups)).is_none());
+        assert!(unsupported_feature(None, Some(Feature::LastStreamMessageType)).is_none());
+    }
+}
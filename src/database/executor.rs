@@ -0,0 +1,154 @@
+use either::Either;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use sqlx::database::HasStatement;
+use sqlx::pool::PoolConnection;
+use sqlx::{Database, Describe, Execute, Executor, PgConnection, PgPool, Postgres, Transaction};
+
+use crate::database::client::{MessageStore, DEFAULT_SCHEMA};
+
+/// An executor which knows the schema containing the message store.
+///
+/// Queries are qualified with the executor's schema, rather than relying on
+/// the connection's `search_path`. A [`MessageStore`] uses its own
+/// [schema](MessageStore::schema), while pools, connections and transactions
+/// use [`DEFAULT_SCHEMA`]. Use [`MessageStore::in_schema`] or
+/// [`InSchema::new`] to use them with a message store in another schema.
+pub trait MessageStoreExecutor<'c>: Executor<'c, Database = Postgres> {
+    /// Returns the schema containing the message store.
+    fn schema(&self) -> &str;
+}
+
+/// An executor used with the message store in a schema.
+///
+/// # Example
+///
+/// ```ignore
+/// use message_db::database::{MessageStore, WriteMessageOpts};
+///
+/// let mut tx = pool.begin().await?;
+/// MessageStore::write_message(
+///     message_store.in_schema(&mut tx),
+///     "account-123",
+///     "Deposited",
+///     &json!({ "amount": 10 }),
+///     &WriteMessageOpts::default(),
+/// )
+/// .await?;
+/// tx.commit().await?;
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct InSchema<'s, E> {
+    executor: E,
+    schema: &'s str,
+}
+
+impl<'s, E> InSchema<'s, E> {
+    /// Uses the executor with the message store in `schema`.
+    pub fn new(executor: E, schema: &'s str) -> Self {
+        InSchema { executor, schema }
+    }
+}
+
+impl MessageStore {
+    /// Uses another executor, such as a transaction, with the message store's
+    /// schema.
+    pub fn in_schema<E>(&self, executor: E) -> InSchema<'_, E> {
+        InSchema::new(executor, self.schema())
+    }
+}
+
+impl<'c> MessageStoreExecutor<'c> for &MessageStore {
+    fn schema(&self) -> &str {
+        MessageStore::schema(self)
+    }
+}
+
+impl<'c, E> MessageStoreExecutor<'c> for InSchema<'_, E>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    fn schema(&self) -> &str {
+        self.schema
+    }
+}
+
+impl<'p> MessageStoreExecutor<'p> for &PgPool {
+    fn schema(&self) -> &str {
+        DEFAULT_SCHEMA
+    }
+}
+
+impl<'c> MessageStoreExecutor<'c> for &'c mut PgConnection {
+    fn schema(&self) -> &str {
+        DEFAULT_SCHEMA
+    }
+}
+
+impl<'c> MessageStoreExecutor<'c> for &'c mut PoolConnection<Postgres> {
+    fn schema(&self) -> &str {
+        DEFAULT_SCHEMA
+    }
+}
+
+impl<'c, 't> MessageStoreExecutor<'t> for &'t mut Transaction<'c, Postgres> {
+    fn schema(&self) -> &str {
+        DEFAULT_SCHEMA
+    }
+}
+
+impl<'c, E> Executor<'c> for InSchema<'_, E>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    type Database = Postgres;
+
+    fn fetch_many<'e, 'q: 'e, Q>(
+        self,
+        query: Q,
+    ) -> BoxStream<
+        'e,
+        Result<
+            Either<<Self::Database as Database>::QueryResult, <Self::Database as Database>::Row>,
+            sqlx::Error,
+        >,
+    >
+    where
+        'c: 'e,
+        Q: 'q + Execute<'q, Self::Database>,
+    {
+        self.executor.fetch_many(query)
+    }
+
+    fn fetch_optional<'e, 'q: 'e, Q>(
+        self,
+        query: Q,
+    ) -> BoxFuture<'e, Result<Option<<Self::Database as Database>::Row>, sqlx::Error>>
+    where
+        'c: 'e,
+        Q: 'q + Execute<'q, Self::Database>,
+    {
+        self.executor.fetch_optional(query)
+    }
+
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [<Self::Database as Database>::TypeInfo],
+    ) -> BoxFuture<'e, Result<<Self::Database as HasStatement<'q>>::Statement, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        self.executor.prepare_with(sql, parameters)
+    }
+
+    fn describe<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+    ) -> BoxFuture<'e, Result<Describe<Self::Database>, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        self.executor.describe(sql)
+    }
+}
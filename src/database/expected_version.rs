@@ -0,0 +1,72 @@
+use std::fmt;
+
+use crate::message::StreamPosition;
+
+/// The version a stream is expected to be at when writing a message.
+///
+/// A stream's version is the position of the last message written to it.
+/// Writing a message with an expected version which does not match the
+/// stream's version fails with
+/// [`Error::WrongExpectedVersion`](crate::Error::WrongExpectedVersion).
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub enum ExpectedVersion {
+    /// The stream may be at any version.
+    #[default]
+    Any,
+    /// The stream must not exist.
+    NoStream,
+    /// The stream must be at exactly this version.
+    Exact(StreamPosition),
+}
+
+impl ExpectedVersion {
+    /// Version representing a stream which does not exist in Message DB's
+    /// `write_message` function.
+    const NO_STREAM: i64 = -1;
+
+    /// Returns the expected version as passed to `write_message`.
+    pub(crate) fn to_sql(self) -> Option<i64> {
+        match self {
+            ExpectedVersion::Any => None,
+            ExpectedVersion::NoStream => Some(Self::NO_STREAM),
+            ExpectedVersion::Exact(position) => Some(position.0),
+        }
+    }
+
+    /// Returns the expected version from a `write_message` version.
+    pub(crate) fn from_sql(version: i64) -> Self {
+        if version == Self::NO_STREAM {
+            ExpectedVersion::NoStream
+        } else {
+            ExpectedVersion::Exact(StreamPosition(version))
+        }
+    }
+}
+
+impl From<StreamPosition> for ExpectedVersion {
+    fn from(position: StreamPosition) -> Self {
+        ExpectedVersion::Exact(position)
+    }
+}
+
+/// Converts a stream's version, as returned by
+/// [`MessageStore::stream_version`](crate::database::MessageStore::stream_version),
+/// into an expected version.
+impl From<Option<StreamPosition>> for ExpectedVersion {
+    fn from(version: Option<StreamPosition>) -> Self {
+        match version {
+            Some(position) => ExpectedVersion::Exact(position),
+            None => ExpectedVersion::NoStream,
+        }
+    }
+}
+
+impl fmt::Display for ExpectedVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpectedVersion::Any => write!(f, "any"),
+            ExpectedVersion::NoStream => write!(f, "no stream"),
+            ExpectedVersion::Exact(position) => write!(f, "{position}"),
+        }
+    }
+}
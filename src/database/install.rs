@@ -0,0 +1,221 @@
+use futures::FutureExt;
+use sqlx::{Executor, PgConnection};
+use tracing::info;
+
+use crate::database::client::{quote_ident, MessageStore, DEFAULT_SCHEMA};
+use crate::database::ServerVersion;
+use crate::{Error, Result};
+
+/// Version of Message DB installed by [`MessageStore::install`] and
+/// [`MessageStore::migrate`].
+pub const MESSAGE_STORE_VERSION: ServerVersion = ServerVersion::new(1, 3, 0);
+
+/// Scripts creating the message store, run in order with the message store's
+/// schema as the search path.
+///
+/// Every script can be run against an existing installation, replacing
+/// functions and views, and creating any missing types, tables and indexes.
+const INSTALL_SCRIPTS: &[&str] = &[
+    include_str!("sql/tables.sql"),
+    include_str!("sql/functions.sql"),
+    include_str!("sql/indexes.sql"),
+    include_str!("sql/views.sql"),
+    include_str!("sql/privileges.sql"),
+    include_str!("sql/search_path.sql"),
+];
+
+/// Scripts run before the install scripts when migrating from a version older
+/// than the one given, in version order.
+///
+/// Each script handles the changes the install scripts cannot, such as
+/// dropping functions whose signature changed in that version.
+const MIGRATIONS: &[(ServerVersion, &str)] = &[
+    (
+        ServerVersion::new(1, 1, 0),
+        include_str!("sql/migrations/1.1.0.sql"),
+    ),
+    (
+        ServerVersion::new(1, 2, 0),
+        include_str!("sql/migrations/1.2.0.sql"),
+    ),
+    (
+        ServerVersion::new(1, 3, 0),
+        include_str!("sql/migrations/1.3.0.sql"),
+    ),
+];
+
+impl MessageStore {
+    /// Installs the message store schema, `messages` table, indexes, server
+    /// functions, views and the `message_store` role.
+    ///
+    /// Everything is created in the message store's
+    /// [schema](MessageStore::schema).
+    ///
+    /// The installation is run in a single transaction. If the message store is
+    /// already installed, [`Error::AlreadyInstalled`] is returned. Use
+    /// [`MessageStore::migrate`] to install or upgrade as needed.
+    pub async fn install(&self) -> Result<()> {
+        self.transaction(|tx| {
+            async move {
+                lock_install(tx).await?;
+                if let Some(version) = installed_version(tx, self.schema()).await? {
+                    return Err(Error::AlreadyInstalled { version });
+                }
+
+                install(tx, self.schema(), None).await
+            }
+            .boxed()
+        })
+        .await?;
+
+        self.set_server_version(MESSAGE_STORE_VERSION);
+
+        Ok(())
+    }
+
+    /// Installs the message store, or upgrades an existing installation to
+    /// [`MESSAGE_STORE_VERSION`].
+    ///
+    /// The installed version is checked with `message_store_version()`. If it
+    /// is newer than [`MESSAGE_STORE_VERSION`], [`Error::UnsupportedVersion`]
+    /// is returned.
+    pub async fn migrate(&self) -> Result<()> {
+        self.transaction(|tx| {
+            async move {
+                lock_install(tx).await?;
+                let Some(version) = installed_version(tx, self.schema()).await? else {
+                    return install(tx, self.schema(), None).await;
+                };
+
+                let installed: ServerVersion = version.parse()?;
+                if installed > MESSAGE_STORE_VERSION {
+                    return Err(Error::UnsupportedVersion { version });
+                }
+                if installed == MESSAGE_STORE_VERSION {
+                    return Ok(());
+                }
+
+                install(tx, self.schema(), Some(installed)).await
+            }
+            .boxed()
+        })
+        .await?;
+
+        self.set_server_version(MESSAGE_STORE_VERSION);
+
+        Ok(())
+    }
+}
+
+/// Returns the installed version of the message store, or `None` if it is not
+/// installed.
+pub(crate) async fn installed_version(
+    conn: &mut PgConnection,
+    schema: &str,
+) -> Result<Option<String>> {
+    let installed: bool = sqlx::query_scalar(
+        "SELECT to_regprocedure(quote_ident($1) || '.message_store_version()') IS NOT NULL",
+    )
+    .bind(schema)
+    .fetch_one(&mut *conn)
+    .await?;
+    if !installed {
+        return Ok(None);
+    }
+
+    let version = sqlx::query_scalar(&format!(
+        "SELECT * FROM {}.message_store_version()",
+        quote_ident(schema)
+    ))
+    .fetch_one(conn)
+    .await?;
+
+    Ok(Some(version))
+}
+
+/// Serializes concurrent installations, such as test suites bootstrapping the
+/// same database.
+async fn lock_install(conn: &mut PgConnection) -> Result<()> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('message_store.install'))")
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+async fn install(conn: &mut PgConnection, schema: &str, from: Option<ServerVersion>) -> Result<()> {
+    let quoted_schema = quote_ident(schema);
+
+    conn.execute(format!("CREATE SCHEMA IF NOT EXISTS {quoted_schema}").as_str())
+        .await?;
+    conn.execute("CREATE EXTENSION IF NOT EXISTS pgcrypto WITH SCHEMA public")
+        .await?;
+    conn.execute(format!("SET LOCAL search_path TO {quoted_schema}, public").as_str())
+        .await?;
+
+    if let Some(from) = from {
+        for (version, script) in migrations(from) {
+            info!(%version, "running message store migration");
+            conn.execute(*script).await?;
+        }
+    }
+
+    for script in INSTALL_SCRIPTS {
+        conn.execute(*script).await?;
+    }
+
+    conn.execute(format!("GRANT USAGE ON SCHEMA {quoted_schema} TO message_store").as_str())
+        .await?;
+    // The role's default search path can only refer to one message store, so
+    // it is left unchanged for stores installed in other schemas.
+    if schema == DEFAULT_SCHEMA {
+        conn.execute(
+            format!("ALTER ROLE message_store SET search_path TO {quoted_schema}, public").as_str(),
+        )
+        .await?;
+    }
+
+    info!(version = %MESSAGE_STORE_VERSION, "installed message store");
+
+    Ok(())
+}
+
+/// Returns the migrations to run when upgrading from an installed version.
+fn migrations(from: ServerVersion) -> impl Iterator<Item = &'static (ServerVersion, &'static str)> {
+    MIGRATIONS
+        .iter()
+        .filter(move |(version, _)| from < *version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migration_versions(from: ServerVersion) -> Vec<String> {
+        migrations(from)
+            .map(|(version, _)| version.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn it_runs_every_later_migration() {
+        assert_eq!(
+            migration_versions(ServerVersion::new(1, 0, 0)),
+            ["1.1.0", "1.2.0", "1.3.0"]
+        );
+        assert_eq!(
+            migration_versions(ServerVersion::new(1, 1, 3)),
+            ["1.2.0", "1.3.0"]
+        );
+        assert_eq!(migration_versions(ServerVersion::new(1, 2, 6)), ["1.3.0"]);
+        assert!(migration_versions(MESSAGE_STORE_VERSION).is_empty());
+    }
+
+    #[test]
+    fn it_orders_migrations_by_version() {
+        assert!(MIGRATIONS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(MIGRATIONS
+            .iter()
+            .all(|(version, _)| *version <= MESSAGE_STORE_VERSION));
+    }
+}
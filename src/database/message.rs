@@ -3,7 +3,7 @@ use serde::Deserialize;
 use serde_json::Value;
 use sqlx::{ColumnIndex, Decode, FromRow, Row, Type};
 
-use crate::message::{Message, Metadata};
+use crate::message::{deserialize_message_data, Message, Metadata};
 use crate::stream_name::StreamName;
 
 impl<'a, R: Row, T> FromRow<'a, R> for Message<T>
@@ -29,15 +29,8 @@ where
                     index: "id".to_string(),
                     source: Box::new(err),
                 })?;
-        let data = row.try_get("data").map(|data: Option<Value>| {
-            serde_json::from_value(data.unwrap_or_default()).map_err(|err| {
-                sqlx::Error::ColumnDecode {
-                    index: "data".to_string(),
-                    source: Box::new(err),
-                }
-            })
-        })??;
-        let metadata =
+        let data: Option<Value> = row.try_get("data")?;
+        let metadata: Metadata =
             row.try_get("metadata")
                 .map(|metadata: Option<Value>| match metadata {
                     Some(metadata) => {
@@ -48,11 +41,21 @@ where
                     }
                     None => Ok(Metadata::default()),
                 })??;
+        let msg_type: String = row.try_get("type")?;
+        let data = deserialize_message_data(
+            &msg_type,
+            metadata.schema_version.as_deref(),
+            data.unwrap_or_default(),
+        )
+        .map_err(|err| sqlx::Error::ColumnDecode {
+            index: "data".to_string(),
+            source: Box::new(err.source),
+        })?;
         let time = Utc.from_utc_datetime(&row.try_get("time")?);
         Ok(Message {
             id,
             stream_name: row.try_get("stream_name")?,
-            msg_type: row.try_get("type")?,
+            msg_type,
             position: row.try_get("position")?,
             global_position: row.try_get("global_position")?,
             data,
@@ -0,0 +1,58 @@
+use sqlx::database::{HasArguments, HasValueRef};
+use sqlx::encode::IsNull;
+use sqlx::postgres::{PgHasArrayType, PgTypeInfo};
+use sqlx::{Database, Decode, Encode, Type};
+
+use crate::message::{GlobalPosition, StreamPosition};
+
+macro_rules! impl_sqlx_position {
+    ($t:ident) => {
+        impl<'q, DB: Database> Encode<'q, DB> for $t
+        where
+            i64: Encode<'q, DB>,
+        {
+            fn encode_by_ref(&self, buf: &mut <DB as HasArguments<'q>>::ArgumentBuffer) -> IsNull {
+                <i64 as Encode<'q, DB>>::encode_by_ref(&self.0, buf)
+            }
+        }
+
+        impl<'r, DB: Database> Decode<'r, DB> for $t
+        where
+            i64: Decode<'r, DB>,
+        {
+            fn decode(
+                value: <DB as HasValueRef<'r>>::ValueRef,
+            ) -> Result<
+                Self,
+                Box<dyn std::error::Error + 'static + ::std::marker::Send + ::std::marker::Sync>,
+            > {
+                <i64 as Decode<'r, DB>>::decode(value).map($t)
+            }
+        }
+
+        impl<DB: Database> Type<DB> for $t
+        where
+            i64: Type<DB>,
+        {
+            fn type_info() -> DB::TypeInfo {
+                <i64 as Type<DB>>::type_info()
+            }
+
+            fn compatible(ty: &DB::TypeInfo) -> ::std::primitive::bool {
+                <i64 as Type<DB>>::compatible(ty)
+            }
+        }
+
+        impl PgHasArrayType for $t
+        where
+            i64: PgHasArrayType,
+        {
+            fn array_type_info() -> PgTypeInfo {
+                <i64 as PgHasArrayType>::array_type_info()
+            }
+        }
+    };
+}
+
+impl_sqlx_position!(StreamPosition);
+impl_sqlx_position!(GlobalPosition);
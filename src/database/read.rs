@@ -0,0 +1,797 @@
+use std::future::Future;
+use std::ops::{Bound, RangeBounds};
+
+use chrono::{DateTime, SubsecRound, Utc};
+use futures::{future, stream, Stream, TryStreamExt};
+use serde::Deserialize;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::database::client::{
+    quote_ident, GetCategoryMessagesOpts, GetStreamMessagesOpts, MessageStore, MESSAGE_COLUMNS,
+};
+use crate::database::executor::MessageStoreExecutor;
+use crate::database::Condition;
+use crate::message::{DeserializeMessage, GenericMessage, GlobalPosition, Message, StreamPosition};
+use crate::{Error, Result};
+
+/// Options for [`MessageStore::read_stream`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, TypedBuilder)]
+pub struct ReadStreamOpts<'a> {
+    #[builder(default, setter(strip_option))]
+    start: Option<StreamPosition>,
+    /// Inclusive.
+    #[builder(default, setter(strip_option))]
+    end: Option<StreamPosition>,
+    #[builder(default, setter(strip_option))]
+    batch_size: Option<i64>,
+    #[builder(default, setter(strip_option))]
+    condition: Option<&'a Condition>,
+}
+
+/// Options for [`MessageStore::read_category`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, TypedBuilder)]
+pub struct ReadCategoryOpts<'a> {
+    #[builder(default, setter(strip_option))]
+    start: Option<GlobalPosition>,
+    /// Inclusive.
+    #[builder(default, setter(strip_option))]
+    end: Option<GlobalPosition>,
+    #[builder(default, setter(strip_option))]
+    batch_size: Option<i64>,
+    #[builder(default, setter(strip_option))]
+    correlation: Option<&'a str>,
+    #[builder(default, setter(strip_option))]
+    consumer_group_member: Option<i64>,
+    #[builder(default, setter(strip_option))]
+    consumer_group_size: Option<i64>,
+    #[builder(default, setter(strip_option))]
+    condition: Option<&'a Condition>,
+}
+
+/// Number of messages retrieved at a time from the `messages` table, matching
+/// the server functions' default batch size.
+pub(crate) const DEFAULT_BATCH_SIZE: i64 = 1000;
+
+/// Options for [`MessageStore::get_all_messages`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, TypedBuilder)]
+pub struct GetAllMessagesOpts<'a> {
+    #[builder(default, setter(strip_option))]
+    position: Option<GlobalPosition>,
+    #[builder(default, setter(strip_option))]
+    batch_size: Option<i64>,
+    #[builder(default, setter(strip_option))]
+    condition: Option<&'a Condition>,
+}
+
+/// Options for [`MessageStore::get_stream_messages_backward`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, TypedBuilder)]
+pub struct GetStreamMessagesBackwardOpts<'a> {
+    /// Inclusive. Defaults to the last message in the stream.
+    #[builder(default, setter(strip_option))]
+    position: Option<StreamPosition>,
+    #[builder(default, setter(strip_option))]
+    batch_size: Option<i64>,
+    #[builder(default, setter(strip_option))]
+    condition: Option<&'a Condition>,
+}
+
+/// Options for [`MessageStore::get_category_messages_backward`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, TypedBuilder)]
+pub struct GetCategoryMessagesBackwardOpts<'a> {
+    /// Inclusive. Defaults to the last message in the category.
+    #[builder(default, setter(strip_option))]
+    position: Option<GlobalPosition>,
+    #[builder(default, setter(strip_option))]
+    batch_size: Option<i64>,
+    #[builder(default, setter(strip_option))]
+    condition: Option<&'a Condition>,
+}
+
+/// Options for [`MessageStore::read_all`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, TypedBuilder)]
+pub struct ReadAllOpts<'a> {
+    #[builder(default, setter(strip_option))]
+    start: Option<GlobalPosition>,
+    /// Inclusive.
+    #[builder(default, setter(strip_option))]
+    end: Option<GlobalPosition>,
+    #[builder(default, setter(strip_option))]
+    batch_size: Option<i64>,
+    #[builder(default, setter(strip_option))]
+    condition: Option<&'a Condition>,
+}
+
+impl MessageStore {
+    /// Reads every message in a stream, optionally between start and end
+    /// positions.
+    ///
+    /// Messages are retrieved in batches of `batch_size` as the stream is
+    /// consumed, and the stream ends after the last message.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use futures::TryStreamExt;
+    /// use message_db::database::{MessageStore, ReadStreamOpts};
+    ///
+    /// let mut messages = MessageStore::read_stream::<AccountEvent, _>(
+    ///     &message_store,
+    ///     "account-123",
+    ///     &ReadStreamOpts::default(),
+    /// );
+    ///
+    /// while let Some(message) = messages.try_next().await? {
+    ///     /* ... */
+    /// }
+    /// ```
+    pub fn read_stream<'a, 'c: 'a, T, E>(
+        executor: E,
+        stream_name: &'a str,
+        opts: &ReadStreamOpts<'a>,
+    ) -> impl Stream<Item = Result<Message<T>>> + 'a
+    where
+        T: for<'de> Deserialize<'de> + 'a,
+        E: 'a + 'c + MessageStoreExecutor<'c> + Clone,
+    {
+        let batch_size = opts.batch_size;
+        let condition = opts.condition;
+        paginate(
+            opts.start,
+            opts.end,
+            batch_size,
+            |message| message.position,
+            StreamPosition::next,
+            move |position| {
+                let executor = executor.clone();
+                async move {
+                    let opts = GetStreamMessagesOpts {
+                        position,
+                        batch_size,
+                        condition,
+                    };
+                    Self::get_stream_messages(executor, stream_name, &opts).await
+                }
+            },
+        )
+    }
+
+    /// Reads every message in a category, optionally between start and end
+    /// global positions.
+    ///
+    /// Messages are retrieved in batches of `batch_size` as the stream is
+    /// consumed, and the stream ends after the last message.
+    ///
+    /// See [`MessageStore::read_stream`].
+    pub fn read_category<'a, 'c: 'a, T, E>(
+        executor: E,
+        category_name: &'a str,
+        opts: &ReadCategoryOpts<'a>,
+    ) -> impl Stream<Item = Result<Message<T>>> + 'a
+    where
+        T: for<'de> Deserialize<'de> + 'a,
+        E: 'a + 'c + MessageStoreExecutor<'c> + Clone,
+    {
+        let opts = opts.clone();
+        paginate(
+            opts.start,
+            opts.end,
+            opts.batch_size,
+            |message| message.global_position,
+            GlobalPosition::next,
+            move |position| {
+                let executor = executor.clone();
+                let opts = GetCategoryMessagesOpts {
+                    position,
+                    batch_size: opts.batch_size,
+                    correlation: opts.correlation,
+                    consumer_group_member: opts.consumer_group_member,
+                    consumer_group_size: opts.consumer_group_size,
+                    condition: opts.condition,
+                };
+                async move { Self::get_category_messages(executor, category_name, &opts).await }
+            },
+        )
+    }
+
+    /// Retrieve messages from every stream in global position order,
+    /// optionally specifying the starting global position, the number of
+    /// messages to retrieve, and a [`Condition`] the messages must match.
+    ///
+    /// Messages are read directly from the `messages` table, so the condition
+    /// does not require
+    /// [`Setting::SqlCondition`](crate::database::Setting::SqlCondition).
+    pub async fn get_all_messages<'e, 'c: 'e, T, E>(
+        executor: E,
+        opts: &GetAllMessagesOpts<'_>,
+    ) -> Result<Vec<Message<T>>>
+    where
+        T: for<'de> Deserialize<'de>,
+        E: 'e + MessageStoreExecutor<'c>,
+    {
+        select_messages(
+            executor,
+            Source::All,
+            opts.position.unwrap_or_default().0,
+            opts.batch_size.unwrap_or(DEFAULT_BATCH_SIZE),
+            opts.condition,
+        )
+        .await
+    }
+
+    /// Reads every message in the message store in global position order,
+    /// optionally between start and end global positions.
+    ///
+    /// Messages are retrieved in batches of `batch_size` as the stream is
+    /// consumed, and the stream ends after the last message.
+    ///
+    /// See [`MessageStore::get_all_messages`].
+    pub fn read_all<'a, 'c: 'a, T, E>(
+        executor: E,
+        opts: &ReadAllOpts<'a>,
+    ) -> impl Stream<Item = Result<Message<T>>> + 'a
+    where
+        T: for<'de> Deserialize<'de> + 'a,
+        E: 'a + 'c + MessageStoreExecutor<'c> + Clone,
+    {
+        let batch_size = opts.batch_size.unwrap_or(DEFAULT_BATCH_SIZE);
+        // The end is applied in the query so that batches are not filled with
+        // messages past it.
+        let condition = match (opts.end, opts.condition) {
+            (Some(end), Some(condition)) => {
+                Some(Condition::global_position(..=end).and(condition.clone()))
+            }
+            (Some(end), None) => Some(Condition::global_position(..=end)),
+            (None, condition) => condition.cloned(),
+        };
+        paginate(
+            opts.start,
+            opts.end,
+            Some(batch_size),
+            |message| message.global_position,
+            GlobalPosition::next,
+            move |position| {
+                let executor = executor.clone();
+                let condition = condition.clone();
+                async move {
+                    let opts = GetAllMessagesOpts {
+                        position,
+                        batch_size: Some(batch_size),
+                        condition: condition.as_ref(),
+                    };
+                    Self::get_all_messages(executor, &opts).await
+                }
+            },
+        )
+    }
+
+    /// Reads the messages in a stream written within the time range.
+    ///
+    /// Messages are read directly from the `messages` table in batches of
+    /// `batch_size`, and do not require
+    /// [`Setting::SqlCondition`](crate::database::Setting::SqlCondition).
+    ///
+    /// Messages are assumed to be written in time order, so reading stops at
+    /// the first message written after the end of the range, rather than
+    /// scanning the remaining messages.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use chrono::{TimeZone, Utc};
+    /// use futures::TryStreamExt;
+    /// use message_db::database::MessageStore;
+    ///
+    /// let start = Utc.with_ymd_and_hms(2022, 12, 1, 9, 0, 0).unwrap();
+    /// let end = Utc.with_ymd_and_hms(2022, 12, 1, 10, 0, 0).unwrap();
+    /// let messages: Vec<Message<AccountEvent>> =
+    ///     MessageStore::read_stream_in_time_range(&message_store, "account-123", start..end, None)
+    ///         .try_collect()
+    ///         .await?;
+    /// ```
+    pub fn read_stream_in_time_range<'a, 'c: 'a, T, E>(
+        executor: E,
+        stream_name: &'a str,
+        range: impl RangeBounds<DateTime<Utc>>,
+        batch_size: Option<i64>,
+    ) -> impl Stream<Item = Result<Message<T>>> + 'a
+    where
+        T: for<'de> Deserialize<'de> + 'a,
+        E: 'a + 'c + MessageStoreExecutor<'c> + Clone,
+    {
+        read_in_time_range(
+            executor,
+            Source::Stream(stream_name),
+            range,
+            batch_size.unwrap_or(DEFAULT_BATCH_SIZE),
+            |message| message.position.0,
+        )
+    }
+
+    /// Reads the messages in a category written within the time range, in
+    /// global position order.
+    ///
+    /// Reading starts at the first global position written at the start of
+    /// the range. See [`MessageStore::first_global_position_at`].
+    ///
+    /// See [`MessageStore::read_stream_in_time_range`].
+    pub fn read_category_in_time_range<'a, 'c: 'a, T, E>(
+        executor: E,
+        category_name: &'a str,
+        range: impl RangeBounds<DateTime<Utc>>,
+        batch_size: Option<i64>,
+    ) -> impl Stream<Item = Result<Message<T>>> + 'a
+    where
+        T: for<'de> Deserialize<'de> + 'a,
+        E: 'a + 'c + MessageStoreExecutor<'c> + Clone,
+    {
+        read_in_time_range(
+            executor,
+            Source::Category(category_name),
+            range,
+            batch_size.unwrap_or(DEFAULT_BATCH_SIZE),
+            |message| message.global_position.0,
+        )
+    }
+
+    /// Reads every message written within the time range, in global position
+    /// order.
+    ///
+    /// Reading starts at the first global position written at the start of
+    /// the range. See [`MessageStore::first_global_position_at`].
+    ///
+    /// See [`MessageStore::read_stream_in_time_range`].
+    pub fn read_all_in_time_range<'a, 'c: 'a, T, E>(
+        executor: E,
+        range: impl RangeBounds<DateTime<Utc>>,
+        batch_size: Option<i64>,
+    ) -> impl Stream<Item = Result<Message<T>>> + 'a
+    where
+        T: for<'de> Deserialize<'de> + 'a,
+        E: 'a + 'c + MessageStoreExecutor<'c> + Clone,
+    {
+        read_in_time_range(
+            executor,
+            Source::All,
+            range,
+            batch_size.unwrap_or(DEFAULT_BATCH_SIZE),
+            |message| message.global_position.0,
+        )
+    }
+
+    /// Returns the global position of the first message written at or after
+    /// the time, or `None` if there is no such message.
+    ///
+    /// This can be used to start a consumer from a point in time.
+    ///
+    /// The `messages` table has no index on `time`, so messages are scanned in
+    /// global position order until the time is reached. The cost grows with
+    /// the number of messages written before the time.
+    pub async fn first_global_position_at<'e, 'c: 'e, E>(
+        executor: E,
+        time: DateTime<Utc>,
+    ) -> Result<Option<GlobalPosition>>
+    where
+        E: 'e + MessageStoreExecutor<'c>,
+    {
+        // Message times are stored in UTC without a time zone.
+        let schema = quote_ident(executor.schema());
+        let position = sqlx::query_scalar(&format!(
+            r#"
+                SELECT global_position
+                FROM {schema}.messages
+                WHERE time >= $1
+                ORDER BY global_position
+                LIMIT 1
+            "#
+        ))
+        .bind(time.naive_utc())
+        .fetch_optional(executor)
+        .await?;
+
+        Ok(position)
+    }
+
+    /// Retrieve messages from a single stream in reverse order, optionally
+    /// specifying the position to read back from, the number of messages to
+    /// retrieve, and a [`Condition`] the messages must match.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use message_db::database::{GetStreamMessagesBackwardOpts, MessageStore};
+    ///
+    /// // The last 10 messages, newest first.
+    /// let messages = MessageStore::get_stream_messages_backward::<AccountEvent, _>(
+    ///     &message_store,
+    ///     "account-123",
+    ///     &GetStreamMessagesBackwardOpts::builder().batch_size(10).build(),
+    /// )
+    /// .await?;
+    /// ```
+    pub async fn get_stream_messages_backward<'e, 'c: 'e, T, E>(
+        executor: E,
+        stream_name: &str,
+        opts: &GetStreamMessagesBackwardOpts<'_>,
+    ) -> Result<Vec<Message<T>>>
+    where
+        T: for<'de> Deserialize<'de>,
+        E: 'e + MessageStoreExecutor<'c>,
+    {
+        let schema = quote_ident(executor.schema());
+        let condition = and_condition(opts.condition);
+        let messages: Vec<GenericMessage> = sqlx::query_as(&format!(
+            r#"
+                SELECT {MESSAGE_COLUMNS}
+                FROM {schema}.messages
+                WHERE stream_name = $1
+                    AND ($2::bigint IS NULL OR position <= $2) {condition}
+                ORDER BY position DESC
+                LIMIT $3
+            "#
+        ))
+        .bind(stream_name)
+        .bind(opts.position)
+        .bind(opts.batch_size.unwrap_or(DEFAULT_BATCH_SIZE))
+        .fetch_all(executor)
+        .await?;
+
+        messages.deserialize_messages()
+    }
+
+    /// Retrieve messages from a category of streams in reverse order,
+    /// optionally specifying the global position to read back from, the
+    /// number of messages to retrieve, and a [`Condition`] the messages must
+    /// match.
+    ///
+    /// See [`MessageStore::get_stream_messages_backward`].
+    pub async fn get_category_messages_backward<'e, 'c: 'e, T, E>(
+        executor: E,
+        category_name: &str,
+        opts: &GetCategoryMessagesBackwardOpts<'_>,
+    ) -> Result<Vec<Message<T>>>
+    where
+        T: for<'de> Deserialize<'de>,
+        E: 'e + MessageStoreExecutor<'c>,
+    {
+        let schema = quote_ident(executor.schema());
+        let condition = and_condition(opts.condition);
+        let messages: Vec<GenericMessage> = sqlx::query_as(&format!(
+            r#"
+                SELECT {MESSAGE_COLUMNS}
+                FROM {schema}.messages
+                WHERE {schema}.category(stream_name) = $1
+                    AND ($2::bigint IS NULL OR global_position <= $2) {condition}
+                ORDER BY global_position DESC
+                LIMIT $3
+            "#
+        ))
+        .bind(category_name)
+        .bind(opts.position)
+        .bind(opts.batch_size.unwrap_or(DEFAULT_BATCH_SIZE))
+        .fetch_all(executor)
+        .await?;
+
+        messages.deserialize_messages()
+    }
+
+    /// Retrieves the message with the id, or `None` if there is no such
+    /// message.
+    pub async fn get_message_by_id<'e, 'c: 'e, T, E>(
+        executor: E,
+        id: Uuid,
+    ) -> Result<Option<Message<T>>>
+    where
+        T: for<'de> Deserialize<'de>,
+        E: 'e + MessageStoreExecutor<'c>,
+    {
+        let schema = quote_ident(executor.schema());
+        let message: Option<GenericMessage> = sqlx::query_as(&format!(
+            "SELECT {MESSAGE_COLUMNS} FROM {schema}.messages WHERE id = $1"
+        ))
+        .bind(id)
+        .fetch_optional(executor)
+        .await?;
+
+        message.deserialize_messages()
+    }
+
+    /// Retrieves the message at the global position, or `None` if there is no
+    /// such message.
+    pub async fn get_message_at_global_position<'e, 'c: 'e, T, E>(
+        executor: E,
+        global_position: GlobalPosition,
+    ) -> Result<Option<Message<T>>>
+    where
+        T: for<'de> Deserialize<'de>,
+        E: 'e + MessageStoreExecutor<'c>,
+    {
+        let schema = quote_ident(executor.schema());
+        let message: Option<GenericMessage> = sqlx::query_as(&format!(
+            "SELECT {MESSAGE_COLUMNS} FROM {schema}.messages WHERE global_position = $1"
+        ))
+        .bind(global_position)
+        .fetch_optional(executor)
+        .await?;
+
+        message.deserialize_messages()
+    }
+}
+
+/// Messages retrieved by [`select_messages`].
+#[derive(Clone, Copy, Debug)]
+enum Source<'a> {
+    /// Messages in a stream, in position order.
+    Stream(&'a str),
+    /// Messages in a category, in global position order.
+    Category(&'a str),
+    /// Every message, in global position order.
+    All,
+}
+
+/// Retrieves a batch of messages from the `messages` table, starting at a
+/// position or global position depending on the source.
+async fn select_messages<'e, 'c: 'e, T, E>(
+    executor: E,
+    source: Source<'_>,
+    position: i64,
+    batch_size: i64,
+    condition: Option<&Condition>,
+) -> Result<Vec<Message<T>>>
+where
+    T: for<'de> Deserialize<'de>,
+    E: 'e + MessageStoreExecutor<'c>,
+{
+    let schema = quote_ident(executor.schema());
+    let (name, filter, order) = match source {
+        Source::Stream(stream_name) => (
+            Some(stream_name),
+            "stream_name = $1 AND".to_string(),
+            "position",
+        ),
+        Source::Category(category_name) => (
+            Some(category_name),
+            format!("{schema}.category(stream_name) = $1 AND"),
+            "global_position",
+        ),
+        Source::All => (None, String::new(), "global_position"),
+    };
+    let condition = and_condition(condition);
+    let messages: Vec<GenericMessage> = sqlx::query_as(&format!(
+        r#"
+            SELECT {MESSAGE_COLUMNS}
+            FROM {schema}.messages
+            WHERE {filter} {order} >= $2 {condition}
+            ORDER BY {order}
+            LIMIT $3
+        "#
+    ))
+    .bind(name)
+    .bind(position)
+    .bind(batch_size)
+    .fetch_all(executor)
+    .await?;
+
+    messages.deserialize_messages()
+}
+
+/// Reads messages from the source written within the time range.
+///
+/// Category and global reads start at the first global position at the start
+/// of the range, rather than scanning every earlier message. Reading stops at
+/// the first message after the end of the range.
+fn read_in_time_range<'a, 'c: 'a, T, E>(
+    executor: E,
+    source: Source<'a>,
+    range: impl RangeBounds<DateTime<Utc>>,
+    batch_size: i64,
+    position: fn(&Message<T>) -> i64,
+) -> impl Stream<Item = Result<Message<T>>> + 'a
+where
+    T: for<'de> Deserialize<'de> + 'a,
+    E: 'a + 'c + MessageStoreExecutor<'c> + Clone,
+{
+    let seek = seek_time(source, range.start_bound());
+    // The end of the range is checked as messages are read, as a condition
+    // would scan every later message looking for a match.
+    let condition = Condition::time((range.start_bound().cloned(), Bound::Unbounded));
+    let end = range.end_bound().map(|end| end.trunc_subsecs(6));
+
+    let messages = paginate(
+        None,
+        None,
+        Some(batch_size),
+        position,
+        |position| position + 1,
+        move |position| {
+            let executor = executor.clone();
+            let condition = condition.clone();
+            async move {
+                let position = match (position, seek) {
+                    (Some(position), _) => position,
+                    (None, Some(start)) => {
+                        match MessageStore::first_global_position_at(executor.clone(), start)
+                            .await?
+                        {
+                            Some(global_position) => global_position.0,
+                            None => return Ok(Vec::new()),
+                        }
+                    }
+                    (None, None) => 0,
+                };
+
+                select_messages(executor, source, position, batch_size, Some(&condition)).await
+            }
+        },
+    );
+
+    until_end(messages, end)
+}
+
+/// Returns the time to seek to before reading from the source, if any.
+///
+/// Streams are read by position, so are never sought.
+fn seek_time(source: Source<'_>, start: Bound<&DateTime<Utc>>) -> Option<DateTime<Utc>> {
+    match (source, start) {
+        (Source::Stream(_), _) | (_, Bound::Unbounded) => None,
+        (_, Bound::Included(start) | Bound::Excluded(start)) => Some(*start),
+    }
+}
+
+/// Ends the messages at the first message written after `end`.
+fn until_end<'a, T>(
+    messages: impl Stream<Item = Result<Message<T>>> + 'a,
+    end: Bound<DateTime<Utc>>,
+) -> impl Stream<Item = Result<Message<T>>> + 'a
+where
+    T: 'a,
+{
+    messages.try_take_while(move |message| {
+        let before_end = match end {
+            Bound::Included(end) => message.time <= end,
+            Bound::Excluded(end) => message.time < end,
+            Bound::Unbounded => true,
+        };
+        future::ready(Ok(before_end))
+    })
+}
+
+/// Returns the condition to append to a WHERE clause, if any.
+fn and_condition(condition: Option<&Condition>) -> String {
+    condition
+        .map(|condition| format!("AND ({condition})"))
+        .unwrap_or_default()
+}
+
+/// Flattens batches of messages retrieved from `start` until `end` or the last
+/// message.
+///
+/// A batch with fewer than `batch_size` messages is assumed to be the last.
+pub(crate) fn paginate<'a, T, P, F, Fut>(
+    start: Option<P>,
+    end: Option<P>,
+    batch_size: Option<i64>,
+    position: fn(&Message<T>) -> P,
+    next: fn(P) -> P,
+    mut fetch: F,
+) -> impl Stream<Item = Result<Message<T>>> + 'a
+where
+    T: 'a,
+    P: Copy + Ord + 'a,
+    F: FnMut(Option<P>) -> Fut + 'a,
+    Fut: Future<Output = Result<Vec<Message<T>>>> + 'a,
+{
+    stream::try_unfold(Some(start), move |page| {
+        let batch = page.map(&mut fetch);
+        async move {
+            let Some(batch) = batch else {
+                return Ok::<_, Error>(None);
+            };
+
+            let mut messages = batch.await?;
+            let len = messages.len();
+            if let Some(end) = end {
+                messages.retain(|message| position(message) <= end);
+            }
+
+            let last = messages.last().map(position);
+            let next_page = match (last, batch_size, end) {
+                (None, _, _) => None,
+                _ if messages.len() < len => None,
+                (_, Some(batch_size), _) if (len as i64) < batch_size => None,
+                (Some(last), _, Some(end)) if last >= end => None,
+                (Some(last), _, _) => Some(Some(next(last))),
+            };
+
+            Ok(Some((messages, next_page)))
+        }
+    })
+    .map_ok(|messages| stream::iter(messages.into_iter().map(Ok)))
+    .try_flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::ops::Bound;
+
+    use chrono::{DateTime, TimeZone, Utc};
+    use futures::{stream, TryStreamExt};
+    use serde_json::Value;
+    use uuid::Uuid;
+
+    use super::{paginate, seek_time, until_end, Source};
+    use crate::message::{GenericMessage, GlobalPosition, Message, Metadata, StreamPosition};
+
+    fn time(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(secs, 0).unwrap()
+    }
+
+    fn message(global_position: i64) -> GenericMessage {
+        Message {
+            id: Uuid::nil(),
+            stream_name: "account-1".parse().unwrap(),
+            msg_type: "Deposited".to_string(),
+            position: StreamPosition(global_position),
+            global_position: GlobalPosition(global_position),
+            data: Value::Null,
+            metadata: Metadata::default(),
+            time: time(global_position),
+        }
+    }
+
+    async fn read_until(end: Bound<DateTime<Utc>>) -> Vec<i64> {
+        let messages = stream::iter((0..5).map(|position| Ok(message(position))));
+        until_end(messages, end)
+            .map_ok(|message| message.global_position.0)
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn it_seeks_category_and_global_reads_to_the_start() {
+        for start in [Bound::Included(&time(10)), Bound::Excluded(&time(10))] {
+            assert_eq!(
+                seek_time(Source::Category("account"), start),
+                Some(time(10))
+            );
+            assert_eq!(seek_time(Source::All, start), Some(time(10)));
+            assert_eq!(seek_time(Source::Stream("account-1"), start), None);
+        }
+        assert_eq!(seek_time(Source::All, Bound::Unbounded), None);
+    }
+
+    #[tokio::test]
+    async fn it_ends_time_range_reads_at_the_end_bound() {
+        assert_eq!(read_until(Bound::Included(time(2))).await, vec![0, 1, 2]);
+        assert_eq!(read_until(Bound::Excluded(time(2))).await, vec![0, 1]);
+        assert_eq!(read_until(Bound::Unbounded).await, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn it_stops_fetching_after_the_end_of_a_time_range() {
+        let fetches = Cell::new(0);
+        let messages = paginate(
+            None,
+            None,
+            Some(2),
+            |message: &GenericMessage| message.global_position.0,
+            |position| position + 1,
+            |position: Option<i64>| {
+                fetches.set(fetches.get() + 1);
+                let start = position.unwrap_or(0);
+                async move { Ok((start..start + 2).map(message).collect()) }
+            },
+        );
+
+        let positions: Vec<i64> = until_end(messages, Bound::Included(time(4)))
+            .map_ok(|message| message.global_position.0)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(positions, vec![0, 1, 2, 3, 4]);
+        assert_eq!(fetches.get(), 3);
+    }
+}
@@ -0,0 +1,296 @@
+use std::borrow::Cow;
+use std::future::Future;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use typed_builder::TypedBuilder;
+
+use crate::database::client::{
+    GetStreamMessagesOpts, MessageBatch, MessageStore, WriteMessageOpts,
+};
+use crate::database::ExpectedVersion;
+use crate::message::{DynMessageType, Message, StreamPosition};
+use crate::{Error, Result};
+
+/// Options for [`MessageStore::write_with_retry`].
+#[derive(Clone, Debug, PartialEq, Eq, TypedBuilder)]
+pub struct RetryOpts {
+    /// Maximum number of attempts, including the first.
+    #[builder(default = 5)]
+    max_attempts: usize,
+    /// Delay before the first retry.
+    #[builder(default = Duration::from_millis(10))]
+    initial_backoff: Duration,
+    /// Upper bound for the delay between retries.
+    #[builder(default = Duration::from_secs(1))]
+    max_backoff: Duration,
+    /// Factor the delay is multiplied by after each retry.
+    #[builder(default = 2)]
+    backoff_multiplier: u32,
+}
+
+impl RetryOpts {
+    /// Returns the delay before the retry following the given attempt,
+    /// starting at `1`.
+    fn backoff(&self, attempt: usize) -> Duration {
+        let exponent = u32::try_from(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.backoff_multiplier
+            .checked_pow(exponent)
+            .and_then(|factor| self.initial_backoff.checked_mul(factor))
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+}
+
+impl Default for RetryOpts {
+    fn default() -> Self {
+        RetryOpts::builder().build()
+    }
+}
+
+impl MessageStore {
+    /// Loads a stream, decides which messages to write, and writes them with
+    /// the stream's version as the expected version.
+    ///
+    /// If the write fails with [`Error::WrongExpectedVersion`] because the
+    /// stream was written to concurrently, the stream is reloaded and `decide`
+    /// is called again, waiting with exponential backoff between attempts.
+    /// After [`RetryOpts`]'s `max_attempts`, [`Error::RetriesExhausted`] is
+    /// returned.
+    ///
+    /// Errors returned by `decide` are returned immediately, and can be of
+    /// any type which message store errors convert into. If `decide` returns
+    /// no messages, nothing is written. Each message's type is taken from
+    /// [`DynMessageType`], so `decide` can return an enum to write messages
+    /// of different types.
+    ///
+    /// Returns the positions of the messages written.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use message_db::database::{MessageStore, RetryOpts};
+    /// use message_db::message::Message;
+    ///
+    /// // `AccountError` implements `From<message_db::Error>`.
+    /// let positions = message_store
+    ///     .write_with_retry(
+    ///         "account-123",
+    ///         &RetryOpts::default(),
+    ///         |events: &[Message<AccountEvent>]| {
+    ///             let balance = Account::from_events(events).balance;
+    ///             if balance < 10 {
+    ///                 return Err(AccountError::InsufficientFunds);
+    ///             }
+    ///             Ok(vec![Withdrawn { amount: 10 }])
+    ///         },
+    ///     )
+    ///     .await?;
+    /// ```
+    pub async fn write_with_retry<T, M, DE, F>(
+        &self,
+        stream_name: &str,
+        opts: &RetryOpts,
+        decide: F,
+    ) -> Result<Vec<StreamPosition>, DE>
+    where
+        T: for<'de> Deserialize<'de>,
+        M: Serialize + DynMessageType,
+        DE: From<Error>,
+        F: FnMut(&[Message<T>]) -> Result<Vec<M>, DE>,
+    {
+        retry_on_conflict(opts, stream_name, decide, |mut decide| async move {
+            let result = self.decide_and_write(stream_name, &mut decide).await;
+            (decide, result)
+        })
+        .await?
+    }
+
+    /// Loads a stream, and writes the messages returned by `decide` with the
+    /// stream's version as the expected version.
+    ///
+    /// Errors returned by `decide` are kept apart from message store errors,
+    /// so that conflicting writes can be retried.
+    async fn decide_and_write<T, M, DE, F>(
+        &self,
+        stream_name: &str,
+        decide: &mut F,
+    ) -> Result<Result<Vec<StreamPosition>, DE>>
+    where
+        T: for<'de> Deserialize<'de>,
+        M: Serialize + DynMessageType,
+        F: FnMut(&[Message<T>]) -> Result<Vec<M>, DE>,
+    {
+        let messages = self.get_all_stream_messages::<T>(stream_name).await?;
+        let expected_version =
+            ExpectedVersion::from(messages.last().map(|message| message.position));
+        let decided = match decide(&messages) {
+            Ok(decided) => decided,
+            Err(err) => return Ok(Err(err)),
+        };
+
+        let mut batch = MessageBatch::default();
+        for (i, message) in decided.iter().enumerate() {
+            let data = serde_json::to_value(message).map_err(Error::SerializeData)?;
+            let opts = if i == 0 {
+                WriteMessageOpts::builder()
+                    .expected_version(expected_version)
+                    .build()
+            } else {
+                WriteMessageOpts::default()
+            };
+            batch.push(
+                message.msg_type(),
+                Cow::Owned(data),
+                &opts.with_schema_version(message.schema_version()),
+            );
+        }
+
+        batch.write_to_store(self, stream_name).await.map(Ok)
+    }
+
+    /// Retrieves all messages from a stream, in batches.
+    async fn get_all_stream_messages<T>(&self, stream_name: &str) -> Result<Vec<Message<T>>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let mut messages: Vec<Message<T>> = Vec::new();
+        loop {
+            let opts = GetStreamMessagesOpts {
+                position: messages.last().map(|message| message.position.next()),
+                ..Default::default()
+            };
+            let batch = Self::get_stream_messages(self, stream_name, &opts).await?;
+            if batch.is_empty() {
+                return Ok(messages);
+            }
+
+            messages.extend(batch);
+        }
+    }
+}
+
+/// Runs `attempt` until it does not fail with [`Error::WrongExpectedVersion`],
+/// waiting with exponential backoff between attempts.
+///
+/// `state` is moved into each attempt and returned with its result, so that
+/// attempts can mutably borrow it across awaits.
+async fn retry_on_conflict<S, R, F, Fut>(
+    opts: &RetryOpts,
+    stream_name: &str,
+    mut state: S,
+    mut attempt: F,
+) -> Result<R>
+where
+    F: FnMut(S) -> Fut,
+    Fut: Future<Output = (S, Result<R>)>,
+{
+    let mut attempts = 1;
+    loop {
+        let result;
+        (state, result) = attempt(state).await;
+        match result {
+            Err(err @ Error::WrongExpectedVersion { .. }) => {
+                if attempts >= opts.max_attempts {
+                    return Err(Error::RetriesExhausted {
+                        attempts,
+                        source: Box::new(err),
+                    });
+                }
+
+                let backoff = opts.backoff(attempts);
+                debug!(%stream_name, attempt = attempts, ?backoff, "retrying conflicting write");
+                tokio::time::sleep(backoff).await;
+                attempts += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    use super::{retry_on_conflict, RetryOpts};
+    use crate::database::ExpectedVersion;
+    use crate::message::StreamPosition;
+    use crate::Error;
+
+    fn wrong_expected_version() -> Error {
+        Error::WrongExpectedVersion {
+            stream_name: "account-123".parse().unwrap(),
+            expected: ExpectedVersion::Exact(StreamPosition(2)),
+            actual: Some(StreamPosition(3)),
+        }
+    }
+
+    #[test]
+    fn it_backs_off_exponentially() {
+        let opts = RetryOpts::builder()
+            .initial_backoff(Duration::from_millis(10))
+            .max_backoff(Duration::from_millis(100))
+            .backoff_multiplier(3)
+            .build();
+
+        assert_eq!(opts.backoff(1), Duration::from_millis(10));
+        assert_eq!(opts.backoff(2), Duration::from_millis(30));
+        assert_eq!(opts.backoff(3), Duration::from_millis(90));
+        assert_eq!(opts.backoff(4), Duration::from_millis(100));
+        assert_eq!(opts.backoff(100), Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn it_retries_after_wrong_expected_version() {
+        let opts = RetryOpts::builder().initial_backoff(Duration::ZERO).build();
+        let attempts = Cell::new(0);
+        let result = retry_on_conflict(&opts, "account-123", (), |()| {
+            attempts.set(attempts.get() + 1);
+            let result = match attempts.get() {
+                1 => Err(wrong_expected_version()),
+                attempt => Ok(attempt),
+            };
+            async move { ((), result) }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 2);
+        assert_eq!(attempts.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn it_stops_after_max_attempts() {
+        let opts = RetryOpts::builder()
+            .max_attempts(3)
+            .initial_backoff(Duration::ZERO)
+            .build();
+        let attempts = Cell::new(0);
+        let result: Result<(), _> = retry_on_conflict(&opts, "account-123", (), |()| {
+            attempts.set(attempts.get() + 1);
+            async { ((), Err(wrong_expected_version())) }
+        })
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::RetriesExhausted { attempts: 3, source })
+                if matches!(*source, Error::WrongExpectedVersion { .. })
+        ));
+        assert_eq!(attempts.get(), 3);
+    }
+
+    #[tokio::test]
+    async fn it_does_not_retry_other_errors() {
+        let opts = RetryOpts::builder().initial_backoff(Duration::ZERO).build();
+        let attempts = Cell::new(0);
+        let result: Result<(), _> = retry_on_conflict(&opts, "account-123", (), |()| {
+            attempts.set(attempts.get() + 1);
+            async { ((), Err(Error::EmptyStreamName)) }
+        })
+        .await;
+
+        assert!(matches!(result, Err(Error::EmptyStreamName)));
+        assert_eq!(attempts.get(), 1);
+    }
+}
@@ -0,0 +1,146 @@
+use std::fmt;
+
+use sqlx::{Executor, Postgres};
+
+use crate::database::client::MessageStore;
+use crate::Result;
+
+/// Message DB settings, which are off unless enabled.
+///
+/// Settings can be enabled for every connection with
+/// [`ConnectOpts`](crate::database::ConnectOpts), or for a single connection
+/// or transaction with [`MessageStore::set_session_setting`] and
+/// [`MessageStore::set_transaction_setting`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Setting {
+    /// Allows the `condition` parameter of `get_stream_messages` and
+    /// `get_category_messages` to be used.
+    ///
+    /// Retrieving messages with a condition when this is off fails with
+    /// [`Error::ConditionNotActivated`](crate::Error::ConditionNotActivated).
+    SqlCondition,
+    /// Raises notices describing every server function call.
+    ///
+    /// Notices are logged by sqlx.
+    Debug,
+    /// Raises notices describing server function calls which retrieve
+    /// messages.
+    DebugGet,
+    /// Raises notices describing server function calls which write messages.
+    DebugWrite,
+}
+
+impl Setting {
+    /// Returns the name of the setting.
+    pub fn name(self) -> &'static str {
+        match self {
+            Setting::SqlCondition => "message_store.sql_condition",
+            Setting::Debug => "message_store.debug",
+            Setting::DebugGet => "message_store.debug_get",
+            Setting::DebugWrite => "message_store.debug_write",
+        }
+    }
+}
+
+/// Returns the value of an enabled or disabled setting.
+pub(crate) fn setting_value(enabled: bool) -> &'static str {
+    if enabled {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+impl fmt::Display for Setting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.name().fmt(f)
+    }
+}
+
+impl MessageStore {
+    /// Enables or disables a setting for the remainder of the session.
+    ///
+    /// The executor should be a single connection or transaction, as the
+    /// setting only applies to the connection it is executed on. To enable a
+    /// setting for every connection in the pool, use
+    /// [`ConnectOpts`](crate::database::ConnectOpts).
+    pub async fn set_session_setting<'e, 'c: 'e, E>(
+        executor: E,
+        setting: Setting,
+        enabled: bool,
+    ) -> Result<()>
+    where
+        E: 'e + Executor<'c, Database = Postgres>,
+    {
+        set_config(executor, setting, enabled, false).await
+    }
+
+    /// Enables or disables a setting until the end of the current transaction.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use message_db::database::{Condition, GetStreamMessagesOpts, MessageStore, Setting};
+    ///
+    /// let messages = message_store
+    ///     .transaction(|tx| {
+    ///         async move {
+    ///             MessageStore::set_transaction_setting(&mut *tx, Setting::SqlCondition, true)
+    ///                 .await?;
+    ///             MessageStore::get_stream_messages::<MessageData, _>(
+    ///                 &mut *tx,
+    ///                 "account-123",
+    ///                 &GetStreamMessagesOpts::builder()
+    ///                     .condition(&Condition::msg_type("Deposited"))
+    ///                     .build(),
+    ///             )
+    ///             .await
+    ///         }
+    ///         .boxed()
+    ///     })
+    ///     .await?;
+    /// ```
+    pub async fn set_transaction_setting<'e, 'c: 'e, E>(
+        executor: E,
+        setting: Setting,
+        enabled: bool,
+    ) -> Result<()>
+    where
+        E: 'e + Executor<'c, Database = Postgres>,
+    {
+        set_config(executor, setting, enabled, true).await
+    }
+
+    /// Returns `true` if a setting is enabled for the session or transaction.
+    pub async fn is_setting_enabled<'e, 'c: 'e, E>(executor: E, setting: Setting) -> Result<bool>
+    where
+        E: 'e + Executor<'c, Database = Postgres>,
+    {
+        let enabled =
+            sqlx::query_scalar("SELECT coalesce(current_setting($1, true) = 'on', false)")
+                .bind(setting.name())
+                .fetch_one(executor)
+                .await?;
+
+        Ok(enabled)
+    }
+}
+
+async fn set_config<'e, 'c: 'e, E>(
+    executor: E,
+    setting: Setting,
+    enabled: bool,
+    is_local: bool,
+) -> Result<()>
+where
+    E: 'e + Executor<'c, Database = Postgres>,
+{
+    sqlx::query("SELECT set_config($1, $2, $3)")
+        .bind(setting.name())
+        .bind(setting_value(enabled))
+        .bind(is_local)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,14 @@
+CREATE INDEX IF NOT EXISTS messages_category ON messages (
+  category(stream_name),
+  global_position,
+  category(metadata->>'correlationStreamName')
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS messages_id ON messages (
+  id
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS messages_stream ON messages (
+  stream_name,
+  position
+);
@@ -0,0 +1,3 @@
+-- get_category_messages gained consumer group parameters before the
+-- condition, which would otherwise be ambiguous with the previous signature.
+DROP FUNCTION IF EXISTS get_category_messages(varchar, bigint, bigint, varchar, varchar);
@@ -0,0 +1,3 @@
+-- The category index includes the correlation category, so an index with the
+-- previous definition is dropped to be recreated by the install scripts.
+DROP INDEX IF EXISTS messages_category;
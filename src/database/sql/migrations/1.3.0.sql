@@ -0,0 +1,3 @@
+-- get_last_stream_message gained an optional type parameter, which would
+-- otherwise be ambiguous with the previous signature.
+DROP FUNCTION IF EXISTS get_last_stream_message(varchar);
@@ -0,0 +1,14 @@
+-- Server functions refer to tables and other functions without a schema.
+-- Fixing their search path resolves them in the message store's schema,
+-- whatever the caller's search path.
+DO $$
+DECLARE
+  server_function regprocedure;
+BEGIN
+  FOR server_function IN
+    SELECT oid FROM pg_proc
+    WHERE pronamespace = (SELECT oid FROM pg_namespace WHERE nspname = current_schema())
+  LOOP
+    EXECUTE format('ALTER FUNCTION %s SET search_path FROM CURRENT', server_function);
+  END LOOP;
+END$$;
@@ -0,0 +1,27 @@
+DO $$
+BEGIN
+  CREATE TYPE message AS (
+    id varchar,
+    stream_name varchar,
+    type varchar,
+    position bigint,
+    global_position bigint,
+    data varchar,
+    metadata varchar,
+    time timestamp
+  );
+EXCEPTION
+  WHEN duplicate_object THEN null;
+END$$;
+
+CREATE TABLE IF NOT EXISTS messages (
+  global_position bigserial NOT NULL,
+  position bigint NOT NULL,
+  time TIMESTAMP WITHOUT TIME ZONE DEFAULT (now() AT TIME ZONE 'utc') NOT NULL,
+  stream_name text NOT NULL,
+  type text NOT NULL,
+  data jsonb,
+  metadata jsonb,
+  id UUID NOT NULL DEFAULT gen_random_uuid(),
+  CONSTRAINT messages_pkey PRIMARY KEY (global_position) NOT DEFERRABLE INITIALLY IMMEDIATE
+);
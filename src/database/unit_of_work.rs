@@ -0,0 +1,129 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use futures::FutureExt;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::database::client::{MessageBatch, MessageStore, WriteMessageOpts};
+use crate::message::{MessageType, StreamPosition};
+use crate::{Error, Result};
+
+/// A unit of work collects messages to be written to multiple streams, and
+/// writes them atomically in a single transaction.
+///
+/// Each stream's messages are written in a single statement. Expected versions
+/// are specified per message using [`WriteMessageOpts`], and are typically set
+/// on the first message written to each stream.
+///
+/// Streams are written in order of their stream names, so that the advisory
+/// locks acquired by `write_message` are always acquired in a consistent order.
+///
+/// # Example
+///
+/// ```ignore
+/// use message_db::database::{UnitOfWork, WriteMessageOpts};
+/// use message_db::message::StreamPosition;
+/// use serde_json::json;
+///
+/// let positions = UnitOfWork::new()
+///     .write(
+///         "account-123",
+///         "Withdrawn",
+///         json!({ "amount": 10 }),
+///         &WriteMessageOpts::builder()
+///             .expected_version(StreamPosition(4))
+///             .build(),
+///     )
+///     .write(
+///         "account:command-456",
+///         "Deposit",
+///         json!({ "amount": 10 }),
+///         &WriteMessageOpts::default(),
+///     )
+///     .commit(&message_store)
+///     .await?;
+///
+/// assert_eq!(positions["account-123"], vec![StreamPosition(5)]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct UnitOfWork {
+    streams: BTreeMap<String, MessageBatch<'static>>,
+}
+
+impl UnitOfWork {
+    /// Creates an empty unit of work.
+    pub fn new() -> Self {
+        UnitOfWork::default()
+    }
+
+    /// Adds a JSON-formatted message to be written to a named stream.
+    ///
+    /// See [`MessageStore::write_message`].
+    pub fn write(
+        &mut self,
+        stream_name: &str,
+        msg_type: &str,
+        data: Value,
+        opts: &WriteMessageOpts<'_>,
+    ) -> &mut Self {
+        self.streams
+            .entry(stream_name.to_string())
+            .or_default()
+            .push(msg_type, Cow::Owned(data), opts);
+        self
+    }
+
+    /// Adds a typed message to be written to a named stream.
+    ///
+    /// See [`MessageStore::write_typed_message`].
+    pub fn write_typed<T>(
+        &mut self,
+        stream_name: &str,
+        data: &T,
+        opts: &WriteMessageOpts<'_>,
+    ) -> Result<&mut Self>
+    where
+        T: Serialize + MessageType,
+    {
+        let data = serde_json::to_value(data).map_err(Error::SerializeData)?;
+        Ok(self.write(
+            stream_name,
+            T::MSG_TYPE,
+            data,
+            &opts.with_schema_version(T::SCHEMA_VERSION),
+        ))
+    }
+
+    /// Returns `true` if no messages have been added.
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    /// Writes all messages in a single transaction.
+    ///
+    /// If any write fails, including due to a wrong expected version, no
+    /// messages are written.
+    ///
+    /// Returns the positions of the messages written, keyed by stream name.
+    pub async fn commit(
+        &self,
+        message_store: &MessageStore,
+    ) -> Result<BTreeMap<String, Vec<StreamPosition>>> {
+        message_store
+            .transaction(|tx| {
+                async move {
+                    let mut positions = BTreeMap::new();
+                    for (stream_name, batch) in &self.streams {
+                        let stream_positions = batch
+                            .write(message_store.in_schema(&mut *tx), stream_name)
+                            .await?;
+                        positions.insert(stream_name.clone(), stream_positions);
+                    }
+                    Ok(positions)
+                }
+                .boxed()
+            })
+            .await
+    }
+}
@@ -0,0 +1,122 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Error;
+
+/// Oldest Message DB version supported by the client.
+pub const MINIMUM_SUPPORTED_VERSION: ServerVersion = ServerVersion::new(1, 0, 0);
+
+/// A Message DB server version, as returned by `message_store_version()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ServerVersion {
+    /// Major version.
+    pub major: u64,
+    /// Minor version.
+    pub minor: u64,
+    /// Patch version.
+    pub patch: u64,
+}
+
+/// Server features which are not available in every supported version.
+///
+/// Calls using a feature which the server does not support return
+/// [`Error::UnsupportedFeature`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Feature {
+    /// Consumer group parameters in `get_category_messages`.
+    ConsumerGroups,
+    /// Filtering `get_last_stream_message` by message type.
+    LastStreamMessageType,
+}
+
+impl ServerVersion {
+    /// Creates a server version.
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        ServerVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Returns `true` if the client supports this server version.
+    ///
+    /// Versions older than [`MINIMUM_SUPPORTED_VERSION`], or with a newer major
+    /// version, are unsupported.
+    pub fn is_supported(self) -> bool {
+        self >= MINIMUM_SUPPORTED_VERSION && self.major == MINIMUM_SUPPORTED_VERSION.major
+    }
+
+    /// Returns `true` if the server version supports a feature.
+    pub fn supports(self, feature: Feature) -> bool {
+        self >= feature.min_version()
+    }
+}
+
+impl Feature {
+    /// Returns the first server version supporting the feature.
+    pub const fn min_version(self) -> ServerVersion {
+        match self {
+            Feature::ConsumerGroups => ServerVersion::new(1, 1, 0),
+            Feature::LastStreamMessageType => ServerVersion::new(1, 3, 0),
+        }
+    }
+}
+
+impl FromStr for ServerVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().splitn(3, '.').map(str::parse);
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch))) => {
+                Ok(ServerVersion::new(major, minor, patch))
+            }
+            _ => Err(Error::UnsupportedVersion {
+                version: s.to_string(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for ServerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Feature::ConsumerGroups => write!(f, "consumer groups"),
+            Feature::LastStreamMessageType => write!(f, "last stream message type"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Feature, ServerVersion};
+
+    #[test]
+    fn it_parses_server_version() {
+        let version: ServerVersion = "1.2.6".parse().unwrap();
+        assert_eq!(version, ServerVersion::new(1, 2, 6));
+        assert_eq!(version.to_string(), "1.2.6");
+
+        assert!("1.2".parse::<ServerVersion>().is_err());
+        assert!("1.2.x".parse::<ServerVersion>().is_err());
+    }
+
+    #[test]
+    fn it_checks_supported_features() {
+        let version = ServerVersion::new(1, 2, 6);
+        assert!(version.is_supported());
+        assert!(version.supports(Feature::ConsumerGroups));
+        assert!(!version.supports(Feature::LastStreamMessageType));
+
+        assert!(!ServerVersion::new(0, 9, 0).is_supported());
+        assert!(!ServerVersion::new(2, 0, 0).is_supported());
+    }
+}
@@ -1,4 +1,12 @@
+use std::fmt;
+
 use thiserror::Error;
+use uuid::Uuid;
+
+#[cfg(feature = "database")]
+use crate::database::{ExpectedVersion, Feature};
+use crate::message::{GlobalPosition, StreamPosition};
+use crate::stream_name::StreamName;
 
 /// Type alias for `Result<T, message_db::Error>`
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -9,18 +17,147 @@ pub enum Error {
     /// Database error.
     #[cfg(feature = "database")]
     #[error(transparent)]
-    Database(#[from] sqlx::Error),
+    Database(sqlx::Error),
+
+    /// A message was written with an expected version which did not match the
+    /// stream's version.
+    #[cfg(feature = "database")]
+    #[error(
+        "wrong expected version {expected} for stream {stream_name} (stream version: {})",
+        actual.map(|actual| actual.to_string()).unwrap_or_else(|| "no stream".to_string())
+    )]
+    WrongExpectedVersion {
+        /// The stream being written to.
+        stream_name: StreamName,
+        /// The expected version of the stream.
+        expected: ExpectedVersion,
+        /// The actual version of the stream, or `None` if the stream does not
+        /// exist.
+        actual: Option<StreamPosition>,
+    },
+
+    /// A message was written with an id which already exists in the message
+    /// store.
+    ///
+    /// See [`WriteMessageOpts`](crate::database::WriteMessageOpts)'s
+    /// `idempotent` option.
+    #[cfg(feature = "database")]
+    #[error("message id {id} already exists")]
+    DuplicateMessageId {
+        /// The duplicate message id.
+        id: Uuid,
+    },
+
+    /// A message does not exist in the message store.
+    #[cfg(feature = "database")]
+    #[error("message {stream_name}/{position} does not exist")]
+    MessageNotFound {
+        /// The message's stream.
+        stream_name: StreamName,
+        /// The message's position in the stream.
+        position: StreamPosition,
+    },
+
+    /// Messages were retrieved with a condition, but the
+    /// [`Setting::SqlCondition`](crate::database::Setting::SqlCondition)
+    /// setting is not enabled.
+    #[cfg(feature = "database")]
+    #[error("retrieval with SQL condition is not activated, enable the message_store.sql_condition setting")]
+    ConditionNotActivated,
+
+    /// A write was attempted the maximum number of times, failing each time
+    /// due to a concurrent write to the stream.
+    #[cfg(feature = "database")]
+    #[error("gave up after {attempts} attempts: {source}")]
+    RetriesExhausted {
+        /// The number of attempts made.
+        attempts: usize,
+        /// The error from the last attempt.
+        source: Box<Error>,
+    },
+
+    /// The message store is already installed.
+    #[cfg(feature = "database")]
+    #[error("message store version {version} is already installed")]
+    AlreadyInstalled {
+        /// The installed version.
+        version: String,
+    },
+
+    /// The message store version is not supported.
+    #[cfg(feature = "database")]
+    #[error("unsupported message store version {version}")]
+    UnsupportedVersion {
+        /// The message store version.
+        version: String,
+    },
+
+    /// A feature is not supported by the message store version.
+    #[cfg(feature = "database")]
+    #[error("{feature} not supported by the message store version")]
+    UnsupportedFeature {
+        /// The unsupported feature.
+        feature: Feature,
+    },
 
     /// Message data failed to deserialize.
     #[error("failed to deserialize data: {0}")]
     DeserializeData(serde_json::Error),
 
+    /// Message data failed to serialize.
+    #[cfg(feature = "database")]
+    #[error("failed to serialize data: {0}")]
+    SerializeData(serde_json::Error),
+
     /// Message metadata failed to deserialize.
     #[cfg(feature = "database")]
     #[error("failed to deserialize metadata: {0}")]
     DeserializeMetadata(serde_json::Error),
 
+    /// A message's data failed to deserialize.
+    #[error("failed to deserialize {message}: {source}")]
+    DeserializeMessage {
+        /// The message which failed to deserialize.
+        message: Box<MessageContext>,
+        /// The deserialization error.
+        source: serde_json::Error,
+    },
+
+    /// A message's type did not match any variant of the data enum.
+    #[error("unknown message type for {message}: {source}")]
+    UnknownMessageType {
+        /// The message with the unknown type.
+        message: Box<MessageContext>,
+        /// The deserialization error.
+        source: serde_json::Error,
+    },
+
     /// Stream name is empty.
     #[error("stream name is empty")]
     EmptyStreamName,
 }
+
+/// Identifying information of a message, attached to errors.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageContext {
+    /// Unique identifier of the message.
+    pub id: Uuid,
+    /// Stream name.
+    pub stream_name: StreamName,
+    /// Message type.
+    pub msg_type: String,
+    /// Position of the message in its stream.
+    pub position: StreamPosition,
+    /// Global position of the message.
+    pub global_position: GlobalPosition,
+}
+
+impl fmt::Display for MessageContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "message {} ({}) at {}/{}",
+            self.id, self.msg_type, self.stream_name, self.position
+        )
+    }
+}
@@ -42,24 +42,150 @@
 //! distinctness provided by namespaces will be eliminated. If you need to
 //! differentiate between classes that have the same name, the name of the
 //! message class should include a prefix or suffix.
+//!
+//! # Reading streams with multiple message types
+//!
+//! Streams typically contain messages of many types. When message data is
+//! deserialized into an enum, the variant is selected by the message's type
+//! rather than by the contents of the data. If the message's metadata contains
+//! a schema version, a variant named `{msg_type}@{schema_version}` is preferred
+//! over one named `{msg_type}`.
+//!
+//! ```
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! enum AccountEvent {
+//!     AccountOpened { initial_balance: i64 },
+//!     Deposited { amount: i64 },
+//!     #[serde(rename = "Deposited@2")]
+//!     DepositedV2 { amount: i64, currency: String },
+//!     AccountClosed,
+//! }
+//! ```
+//!
+//! If no variant matches the message type, the data is deserialized using the
+//! enum's regular serde representation.
 
+mod de;
 mod metadata;
+mod position;
 
 use chrono::serde::ts_milliseconds;
 use chrono::{DateTime, Utc};
+#[cfg(feature = "derive")]
+pub use message_db_derive::Message;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
 
+pub(crate) use self::de::deserialize_message_data;
+#[cfg(feature = "database")]
+pub(crate) use self::metadata::message_identifier;
 pub use self::metadata::{Metadata, MetadataRef};
+pub use self::position::{GlobalPosition, StreamPosition};
 use crate::stream_name::StreamName;
-use crate::{Error, Result};
+use crate::{Error, MessageContext, Result};
 
 /// Generic message JSON data.
 pub type MessageData = Value;
 /// A generic message with any JSON data.
 pub type GenericMessage = Message<MessageData>;
 
+/// A message data type with a known message type name.
+///
+/// Implementing this trait allows message data to be written with
+/// [`MessageStore::write_typed_message`](crate::database::MessageStore::write_typed_message),
+/// which fills in the message type and schema version automatically.
+///
+/// With the `derive` feature enabled, this trait can be implemented with
+/// `#[derive(Message)]`.
+///
+/// # Example
+///
+/// ```
+/// use message_db::message::MessageType;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct AccountOpened {
+///     initial_balance: i64,
+/// }
+///
+/// impl MessageType for AccountOpened {
+///     const MSG_TYPE: &'static str = "AccountOpened";
+///     const SCHEMA_VERSION: Option<&'static str> = Some("1");
+/// }
+/// ```
+pub trait MessageType {
+    /// Message type name.
+    ///
+    /// For commands, this is typically the command name.
+    /// For events, this is typically the event name.
+    const MSG_TYPE: &'static str;
+
+    /// Version identifier of the message schema.
+    ///
+    /// When set, this is written to the message's
+    /// [`Metadata::schema_version`] unless the metadata already specifies
+    /// one.
+    const SCHEMA_VERSION: Option<&'static str> = None;
+}
+
+/// Message data whose message type depends on the value, such as an enum of
+/// events.
+///
+/// This is implemented for every [`MessageType`], and allows
+/// [`MessageStore::write_with_retry`](crate::database::MessageStore::write_with_retry)
+/// to write messages of different types.
+///
+/// # Example
+///
+/// ```
+/// use message_db::message::DynMessageType;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// #[serde(untagged)]
+/// enum AccountEvent {
+///     Deposited { amount: i64 },
+///     Withdrawn { amount: i64 },
+/// }
+///
+/// impl DynMessageType for AccountEvent {
+///     fn msg_type(&self) -> &str {
+///         match self {
+///             AccountEvent::Deposited { .. } => "Deposited",
+///             AccountEvent::Withdrawn { .. } => "Withdrawn",
+///         }
+///     }
+/// }
+/// ```
+pub trait DynMessageType {
+    /// Message type name of the value.
+    fn msg_type(&self) -> &str;
+
+    /// Version identifier of the value's message schema.
+    ///
+    /// See [`MessageType::SCHEMA_VERSION`].
+    fn schema_version(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl<T> DynMessageType for T
+where
+    T: MessageType,
+{
+    fn msg_type(&self) -> &str {
+        T::MSG_TYPE
+    }
+
+    fn schema_version(&self) -> Option<&str> {
+        T::SCHEMA_VERSION
+    }
+}
+
 /// A message used with the message store, containing data `T`.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Message<T> {
@@ -73,11 +199,11 @@ pub struct Message<T> {
     /// For events, this is typically the event name.
     pub msg_type: String,
     /// An incrementing gapless squence in the stream.
-    pub position: i64,
+    pub position: StreamPosition,
     /// Global incrementing sequence.
     ///
     /// This may contain gaps.
-    pub global_position: i64,
+    pub global_position: GlobalPosition,
     /// Message data.
     pub data: T,
     /// Message metadata.
@@ -93,7 +219,7 @@ impl<T> Message<T> {
     /// # Example
     ///
     /// ```
-    /// # use message_db::message::{Message, Metadata};
+    /// # use message_db::message::{GlobalPosition, Message, Metadata, StreamPosition};
     /// # use chrono::Utc;
     /// # use uuid::Uuid;
     /// #
@@ -102,8 +228,8 @@ impl<T> Message<T> {
     /// #     id: Uuid::new_v4(),
     /// #     stream_name: "category-id".parse().unwrap(),
     /// #     msg_type: "foo".to_string(),
-    /// #     position: 0,
-    /// #     global_position: 0,
+    /// #     position: StreamPosition(0),
+    /// #     global_position: GlobalPosition(1),
     /// #     data: Foo { num: 10 },
     /// #     metadata: Metadata::default(),
     /// #     time: Utc::now(),
@@ -133,15 +259,34 @@ impl<T> Message<T> {
             time: self.time,
         }
     }
+
+    /// Returns the identifying information of the message.
+    pub fn context(&self) -> MessageContext {
+        MessageContext {
+            id: self.id,
+            stream_name: self.stream_name.clone(),
+            msg_type: self.msg_type.clone(),
+            position: self.position,
+            global_position: self.global_position,
+        }
+    }
 }
 
 impl GenericMessage {
     /// Deserializes message data into `T`, returning a new `Message<T>`.
+    ///
+    /// If `T` is an enum, the variant is selected by the message type and
+    /// schema version. See the [module level docs](self) for more information.
     pub fn deserialize_data<T>(self) -> Result<Message<T>, serde_json::Error>
     where
         T: for<'de> Deserialize<'de>,
     {
-        let data = serde_json::from_value(self.data)?;
+        let data = deserialize_message_data(
+            &self.msg_type,
+            self.metadata.schema_version.as_deref(),
+            self.data,
+        )
+        .map_err(|err| err.source)?;
         Ok(Message {
             id: self.id,
             stream_name: self.stream_name,
@@ -153,6 +298,99 @@ impl GenericMessage {
             time: self.time,
         })
     }
+
+    /// Deserializes message data into `T`, returning a new `Message<T>`.
+    ///
+    /// Unlike [`GenericMessage::deserialize_data`], errors contain the
+    /// identifying information of the message. If `T` is an enum with no
+    /// variant matching the message type, [`Error::UnknownMessageType`] is
+    /// returned, otherwise [`Error::DeserializeMessage`].
+    pub fn try_deserialize_data<T>(mut self) -> Result<Message<T>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let data = deserialize_message_data(
+            &self.msg_type,
+            self.metadata.schema_version.as_deref(),
+            std::mem::take(&mut self.data),
+        );
+        match data {
+            Ok(data) => Ok(self.map_data(|_| data)),
+            Err(err) if err.unknown_type => Err(Error::UnknownMessageType {
+                message: Box::new(self.context()),
+                source: err.source,
+            }),
+            Err(err) => Err(Error::DeserializeMessage {
+                message: Box::new(self.context()),
+                source: err.source,
+            }),
+        }
+    }
+}
+
+/// Determines how messages with an unknown type are handled by
+/// [`DeserializeEach::deserialize_each`] and
+/// [`CategoryStream::each`](crate::database::CategoryStream::each).
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub enum UnknownTypePolicy {
+    /// Unknown message types result in an [`Error::UnknownMessageType`].
+    #[default]
+    Error,
+    /// Messages with an unknown type are skipped.
+    Skip,
+}
+
+/// Deserializes messages individually, so that a single malformed message
+/// does not fail an entire batch.
+///
+/// Category subscriptions can also be consumed one message at a time with
+/// [`CategoryStream::each`](crate::database::CategoryStream::each).
+///
+/// # Example
+///
+/// ```ignore
+/// use futures::StreamExt;
+/// use message_db::database::{MessageStore, SubscribeToCategoryOpts};
+/// use message_db::message::{DeserializeEach, MessageData, UnknownTypePolicy};
+///
+/// let mut stream = MessageStore::subscribe_to_category::<MessageData, _>(
+///     &message_store,
+///     "account",
+///     &SubscribeToCategoryOpts::default(),
+/// )
+/// .await?;
+///
+/// while let Some(messages) = stream.next().await {
+///     for message in messages?.deserialize_each::<AccountEvent>(UnknownTypePolicy::Skip) {
+///         match message {
+///             Ok(message) => { /* ... */ }
+///             Err(err) => error!("{err}"),
+///         }
+///     }
+/// }
+/// ```
+pub trait DeserializeEach {
+    /// Deserializes each message's data into `T`.
+    ///
+    /// See [`GenericMessage::try_deserialize_data`].
+    fn deserialize_each<T>(self, unknown_types: UnknownTypePolicy) -> Vec<Result<Message<T>>>
+    where
+        T: for<'de> Deserialize<'de>;
+}
+
+impl DeserializeEach for Vec<GenericMessage> {
+    fn deserialize_each<T>(self, unknown_types: UnknownTypePolicy) -> Vec<Result<Message<T>>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        self.into_iter()
+            .map(|message| message.try_deserialize_data())
+            .filter(|result| {
+                !(unknown_types == UnknownTypePolicy::Skip
+                    && matches!(result, Err(Error::UnknownMessageType { .. })))
+            })
+            .collect()
+    }
 }
 
 pub(crate) trait DeserializeMessage<T>
@@ -190,3 +428,81 @@ where
             .map_err(Error::DeserializeData)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use uuid::Uuid;
+
+    use super::{
+        DeserializeEach, GenericMessage, GlobalPosition, Message, Metadata, StreamPosition,
+        UnknownTypePolicy,
+    };
+    use crate::Error;
+
+    #[derive(Debug, PartialEq, Eq, Deserialize)]
+    enum Event {
+        Deposited { amount: i64 },
+        Withdrawn { amount: i64 },
+    }
+
+    fn message(position: i64, msg_type: &str, data: Value) -> GenericMessage {
+        Message {
+            id: Uuid::new_v4(),
+            stream_name: "account-123".parse().unwrap(),
+            msg_type: msg_type.to_string(),
+            position: StreamPosition(position),
+            global_position: GlobalPosition(position + 10),
+            data,
+            metadata: Metadata::default(),
+            time: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn it_skips_only_unknown_message_types() {
+        let messages = vec![
+            message(0, "Deposited", json!({ "amount": 10 })),
+            message(1, "Renamed", json!({ "name": "savings" })),
+            message(2, "Withdrawn", json!({ "amount": "five" })),
+            message(3, "Withdrawn", json!({ "amount": 5 })),
+        ];
+
+        let results = messages
+            .clone()
+            .deserialize_each::<Event>(UnknownTypePolicy::Error);
+        assert_eq!(results.len(), 4);
+        assert!(matches!(results[1], Err(Error::UnknownMessageType { .. })));
+        assert!(matches!(results[2], Err(Error::DeserializeMessage { .. })));
+
+        let results = messages.deserialize_each::<Event>(UnknownTypePolicy::Skip);
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[0].as_ref().unwrap().data,
+            Event::Deposited { amount: 10 }
+        );
+        assert!(matches!(results[1], Err(Error::DeserializeMessage { .. })));
+        assert_eq!(
+            results[2].as_ref().unwrap().data,
+            Event::Withdrawn { amount: 5 }
+        );
+    }
+
+    #[test]
+    fn it_returns_message_context_on_deserialize_error() {
+        let message = message(2, "Withdrawn", json!({ "amount": "five" }));
+        let id = message.id;
+
+        let err = message.try_deserialize_data::<Event>().unwrap_err();
+        let Error::DeserializeMessage { message, .. } = err else {
+            panic!("expected deserialize message error, got {err:?}");
+        };
+        assert_eq!(message.id, id);
+        assert_eq!(message.stream_name.to_string(), "account-123");
+        assert_eq!(message.msg_type, "Withdrawn");
+        assert_eq!(message.position, StreamPosition(2));
+        assert_eq!(message.global_position, GlobalPosition(12));
+    }
+}
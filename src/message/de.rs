@@ -0,0 +1,279 @@
+use std::cell::Cell;
+
+use serde::de::value::StringDeserializer;
+use serde::de::{
+    DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, VariantAccess, Visitor,
+};
+use serde::Deserialize;
+use serde_json::{Error, Value};
+
+/// Separator between a message type and schema version in enum variant names.
+///
+/// # Example
+///
+/// `AccountOpened@2`
+pub(crate) const SCHEMA_VERSION_SEPARATOR: char = '@';
+
+/// Error returned by [`deserialize_message_data`].
+#[derive(Debug)]
+pub(crate) struct DataError {
+    /// Whether `T` is an enum with no variant matching the message type.
+    pub(crate) unknown_type: bool,
+    pub(crate) source: Error,
+}
+
+/// Deserializes message data into `T`, selecting enum variants by the message
+/// type.
+///
+/// If `T` is an enum with a variant named `{msg_type}@{schema_version}`, or
+/// otherwise `{msg_type}`, the data is deserialized as the contents of that
+/// variant. If no variant matches, the data is deserialized as is.
+pub(crate) fn deserialize_message_data<T>(
+    msg_type: &str,
+    schema_version: Option<&str>,
+    data: Value,
+) -> Result<T, DataError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let unknown_type = Cell::new(false);
+    T::deserialize(MessageDataDeserializer {
+        msg_type,
+        schema_version,
+        data,
+        unknown_type: &unknown_type,
+    })
+    .map_err(|source| DataError {
+        unknown_type: unknown_type.get(),
+        source,
+    })
+}
+
+struct MessageDataDeserializer<'a> {
+    msg_type: &'a str,
+    schema_version: Option<&'a str>,
+    data: Value,
+    unknown_type: &'a Cell<bool>,
+}
+
+impl<'a> MessageDataDeserializer<'a> {
+    fn variant(&self, variants: &[&str]) -> Option<String> {
+        self.schema_version
+            .map(|schema_version| {
+                format!(
+                    "{}{SCHEMA_VERSION_SEPARATOR}{schema_version}",
+                    self.msg_type
+                )
+            })
+            .filter(|variant| variants.contains(&variant.as_str()))
+            .or_else(|| {
+                variants
+                    .contains(&self.msg_type)
+                    .then(|| self.msg_type.to_string())
+            })
+    }
+}
+
+macro_rules! forward_to_data {
+    ($( $method:ident ( $( $arg:ident : $ty:ty ),* ) )*) => {
+        $(
+            fn $method<V>(self, $( $arg: $ty, )* visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.data.$method($( $arg, )* visitor)
+            }
+        )*
+    };
+}
+
+impl<'a, 'de> Deserializer<'de> for MessageDataDeserializer<'a> {
+    type Error = Error;
+
+    forward_to_data! {
+        deserialize_any()
+        deserialize_bool()
+        deserialize_i8()
+        deserialize_i16()
+        deserialize_i32()
+        deserialize_i64()
+        deserialize_i128()
+        deserialize_u8()
+        deserialize_u16()
+        deserialize_u32()
+        deserialize_u64()
+        deserialize_u128()
+        deserialize_f32()
+        deserialize_f64()
+        deserialize_char()
+        deserialize_str()
+        deserialize_string()
+        deserialize_bytes()
+        deserialize_byte_buf()
+        deserialize_option()
+        deserialize_unit()
+        deserialize_unit_struct(name: &'static str)
+        deserialize_newtype_struct(name: &'static str)
+        deserialize_seq()
+        deserialize_tuple(len: usize)
+        deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_map()
+        deserialize_struct(name: &'static str, fields: &'static [&'static str])
+        deserialize_identifier()
+        deserialize_ignored_any()
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.variant(variants) {
+            Some(variant) => visitor.visit_enum(MessageEnumAccess {
+                variant,
+                data: self.data,
+            }),
+            None => {
+                self.unknown_type.set(true);
+                self.data.deserialize_enum(name, variants, visitor)
+            }
+        }
+    }
+}
+
+struct MessageEnumAccess {
+    variant: String,
+    data: Value,
+}
+
+impl<'de> EnumAccess<'de> for MessageEnumAccess {
+    type Error = Error;
+    type Variant = MessageVariantAccess;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant: StringDeserializer<Error> = self.variant.into_deserializer();
+        let value = seed.deserialize(variant)?;
+        Ok((value, MessageVariantAccess { data: self.data }))
+    }
+}
+
+struct MessageVariantAccess {
+    data: Value,
+}
+
+impl<'de> VariantAccess<'de> for MessageVariantAccess {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.data)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.data.deserialize_tuple(len, visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.data.deserialize_struct("", fields, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::deserialize_message_data;
+
+    #[derive(Debug, PartialEq, Eq, Deserialize)]
+    struct Deposited {
+        amount: i64,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Deserialize)]
+    enum Event {
+        AccountOpened,
+        Deposited(Deposited),
+        #[serde(rename = "Deposited@2")]
+        DepositedV2 {
+            amount: i64,
+            currency: String,
+        },
+        Withdrawn {
+            amount: i64,
+        },
+    }
+
+    #[test]
+    fn it_selects_variant_by_msg_type() {
+        let event: Event =
+            deserialize_message_data("AccountOpened", None, json!({ "ignored": true })).unwrap();
+        assert_eq!(event, Event::AccountOpened);
+
+        let event: Event =
+            deserialize_message_data("Deposited", None, json!({ "amount": 10 })).unwrap();
+        assert_eq!(event, Event::Deposited(Deposited { amount: 10 }));
+
+        let event: Event =
+            deserialize_message_data("Withdrawn", Some("3"), json!({ "amount": 5 })).unwrap();
+        assert_eq!(event, Event::Withdrawn { amount: 5 });
+    }
+
+    #[test]
+    fn it_selects_versioned_variant() {
+        let event: Event = deserialize_message_data(
+            "Deposited",
+            Some("2"),
+            json!({ "amount": 10, "currency": "AUD" }),
+        )
+        .unwrap();
+        assert_eq!(
+            event,
+            Event::DepositedV2 {
+                amount: 10,
+                currency: "AUD".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn it_falls_back_to_data() {
+        let event: Event =
+            deserialize_message_data("Unknown", None, json!({ "Withdrawn": { "amount": 5 } }))
+                .unwrap();
+        assert_eq!(event, Event::Withdrawn { amount: 5 });
+
+        let deposited: Deposited =
+            deserialize_message_data("Deposited", None, json!({ "amount": 10 })).unwrap();
+        assert_eq!(deposited, Deposited { amount: 10 });
+
+        let err = deserialize_message_data::<Event>("Unknown", None, json!({})).unwrap_err();
+        assert!(err.unknown_type);
+
+        let err = deserialize_message_data::<Event>("Deposited", None, json!({})).unwrap_err();
+        assert!(!err.unknown_type);
+    }
+}
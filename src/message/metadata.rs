@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::message::{GlobalPosition, StreamPosition};
 use crate::stream_name::{Category, StreamName};
 
 /// A message's metadata object contains information about the stream where the
@@ -24,20 +25,20 @@ pub struct Metadata {
     pub stream_name: Option<StreamName>,
     /// The sequential position of the message in its stream.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub position: Option<i64>,
+    pub position: Option<StreamPosition>,
     /// The sequential position of the message in the entire message store.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub global_position: Option<i64>,
+    pub global_position: Option<GlobalPosition>,
     /// The stream name of the message that precedes the message in a sequential
     /// [message flow](http://docs.eventide-project.org/user-guide/messages-and-message-data/messages.html#message-workflows).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub causation_message_stream_name: Option<StreamName>,
     /// The sequential position of the causation message in its stream.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub causation_message_position: Option<i64>,
+    pub causation_message_position: Option<StreamPosition>,
     /// The sequential position of the message in the entire message store.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub causation_message_global_position: Option<i64>,
+    pub causation_message_global_position: Option<GlobalPosition>,
     /// Name of the stream that represents an encompassing business process that
     /// coordinates the sub-process that the message is a part of.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -70,20 +71,20 @@ pub struct MetadataRef<'a> {
     pub stream_name: Option<&'a StreamName>,
     /// The sequential position of the message in its stream.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub position: Option<i64>,
+    pub position: Option<StreamPosition>,
     /// The sequential position of the message in the entire message store.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub global_position: Option<i64>,
+    pub global_position: Option<GlobalPosition>,
     /// The stream name of the message that precedes the message in a sequential
     /// [message flow](http://docs.eventide-project.org/user-guide/messages-and-message-data/messages.html#message-workflows).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub causation_message_stream_name: Option<&'a StreamName>,
     /// The sequential position of the causation message in its stream.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub causation_message_position: Option<i64>,
+    pub causation_message_position: Option<StreamPosition>,
     /// The sequential position of the message in the entire message store.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub causation_message_global_position: Option<i64>,
+    pub causation_message_global_position: Option<GlobalPosition>,
     /// Name of the stream that represents an encompassing business process that
     /// coordinates the sub-process that the message is a part of.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -112,7 +113,7 @@ impl Metadata {
     /// stream_name/position.
     pub fn identifier(&self) -> Option<String> {
         Option::zip(self.stream_name.as_ref(), self.position)
-            .map(|(stream_name, position)| format!("{stream_name}/{position}"))
+            .map(|(stream_name, position)| message_identifier(stream_name, position))
     }
 
     /// The unique identifier for a message's causation message is a combination
@@ -126,7 +127,7 @@ impl Metadata {
             self.causation_message_stream_name.as_ref(),
             self.causation_message_position,
         )
-        .map(|(stream_name, position)| format!("{stream_name}/{position}"))
+        .map(|(stream_name, position)| message_identifier(stream_name, position))
     }
 
     /// When messages represent subsequent steps in a workflow, a subsequent
@@ -254,3 +255,11 @@ impl TryFrom<Option<Value>> for Metadata {
         }
     }
 }
+
+/// Returns the identifier of the message at the position in the stream, of the
+/// form `stream_name/position`.
+///
+/// See [`Metadata::identifier`].
+pub(crate) fn message_identifier(stream_name: &StreamName, position: StreamPosition) -> String {
+    format!("{stream_name}/{position}")
+}
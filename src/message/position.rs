@@ -0,0 +1,54 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// The position of a message within its stream.
+///
+/// Stream positions are an incrementing gapless sequence starting at `0`.
+#[derive(
+    Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct StreamPosition(pub i64);
+
+/// The position of a message within the entire message store.
+///
+/// Global positions are an incrementing sequence starting at `1`, which may
+/// contain gaps.
+#[derive(
+    Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct GlobalPosition(pub i64);
+
+macro_rules! impl_position {
+    ($t:ident) => {
+        impl $t {
+            /// Returns the position following this one.
+            pub fn next(self) -> Self {
+                $t(self.0 + 1)
+            }
+        }
+
+        impl fmt::Display for $t {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl From<i64> for $t {
+            fn from(position: i64) -> Self {
+                $t(position)
+            }
+        }
+
+        impl From<$t> for i64 {
+            fn from(position: $t) -> Self {
+                position.0
+            }
+        }
+    };
+}
+
+impl_position!(StreamPosition);
+impl_position!(GlobalPosition);
@@ -0,0 +1,31 @@
+//! Helpers for tests against a Message DB server.
+//!
+//! These tests are ignored by default. Run them with
+//! `cargo test -- --ignored` and `DATABASE_URL` set to a database the message
+//! store can be installed in. Each test installs the message store in a new
+//! schema, which is dropped afterwards.
+
+use std::env;
+
+use message_db::database::{ConnectOpts, MessageStore};
+use uuid::Uuid;
+
+/// Connects to a message store installed in a new schema.
+pub async fn message_store() -> MessageStore {
+    let url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let schema = format!("test_{}", Uuid::new_v4().simple());
+    let message_store =
+        MessageStore::connect_with(&url, &ConnectOpts::builder().schema(&schema).build())
+            .await
+            .unwrap();
+    message_store.install().await.unwrap();
+    message_store
+}
+
+/// Drops the message store's schema.
+pub async fn drop_schema(message_store: MessageStore) {
+    sqlx::query(&format!("DROP SCHEMA {} CASCADE", message_store.schema()))
+        .execute(message_store.pool())
+        .await
+        .unwrap();
+}
@@ -0,0 +1,105 @@
+//! Category subscriptions against a Message DB server. See [`common`].
+
+mod common;
+
+use futures::StreamExt;
+use message_db::database::{MessageStore, SubscribeToCategoryOpts, WriteMessageOpts};
+use message_db::message::UnknownTypePolicy;
+use message_db::Error;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::common::{drop_schema, message_store};
+
+#[derive(Debug, Deserialize, PartialEq)]
+enum AccountEvent {
+    Deposited { amount: i64 },
+}
+
+/// Writes a valid message, a malformed message, a message of an unknown type
+/// and another valid message.
+async fn write_messages(message_store: &MessageStore) {
+    let messages = [
+        ("Deposited", json!({ "amount": 10 })),
+        ("Deposited", json!({ "amount": "ten" })),
+        ("Opened", json!({})),
+        ("Deposited", json!({ "amount": 20 })),
+    ];
+    for (msg_type, data) in messages {
+        MessageStore::write_message(
+            message_store,
+            "account-1",
+            msg_type,
+            &data,
+            &WriteMessageOpts::default(),
+        )
+        .await
+        .unwrap();
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires a Message DB server at DATABASE_URL"]
+async fn it_fails_batches_containing_a_bad_message() {
+    let message_store = message_store().await;
+    write_messages(&message_store).await;
+
+    let mut stream = MessageStore::subscribe_to_category::<AccountEvent, _>(
+        &message_store,
+        "account",
+        &SubscribeToCategoryOpts::default(),
+    )
+    .await
+    .unwrap();
+
+    assert!(stream.next().await.unwrap().is_err());
+    drop(stream);
+
+    drop_schema(message_store).await;
+}
+
+#[tokio::test]
+#[ignore = "requires a Message DB server at DATABASE_URL"]
+async fn it_consumes_each_message_with_an_unknown_type_policy() {
+    let message_store = message_store().await;
+    write_messages(&message_store).await;
+
+    let results: Vec<_> = MessageStore::subscribe_to_category::<AccountEvent, _>(
+        &message_store,
+        "account",
+        &SubscribeToCategoryOpts::default(),
+    )
+    .await
+    .unwrap()
+    .each(UnknownTypePolicy::Skip)
+    .take(3)
+    .collect()
+    .await;
+
+    assert_eq!(
+        results[0].as_ref().unwrap().data,
+        AccountEvent::Deposited { amount: 10 }
+    );
+    assert!(matches!(results[1], Err(Error::DeserializeMessage { .. })));
+    assert_eq!(
+        results[2].as_ref().unwrap().data,
+        AccountEvent::Deposited { amount: 20 }
+    );
+
+    let results: Vec<_> = MessageStore::subscribe_to_category::<AccountEvent, _>(
+        &message_store,
+        "account",
+        &SubscribeToCategoryOpts::default(),
+    )
+    .await
+    .unwrap()
+    .each(UnknownTypePolicy::Error)
+    .take(4)
+    .collect()
+    .await;
+
+    assert!(matches!(results[2], Err(Error::UnknownMessageType { .. })));
+    assert_eq!(results.into_iter().filter(Result::is_ok).count(), 2);
+
+    drop_schema(message_store).await;
+}
@@ -0,0 +1,112 @@
+//! Migrations against a Message DB server. See [`common`].
+
+mod common;
+
+use message_db::database::{GetCategoryMessagesOpts, MessageStore, WriteMessageOpts};
+use message_db::message::MessageData;
+use serde_json::json;
+use sqlx::Executor;
+
+use crate::common::{drop_schema, message_store};
+
+/// Reverts the message store's version, function signatures and category
+/// index to those of Message DB 1.0.0.
+async fn downgrade_to_1_0_0(message_store: &MessageStore) {
+    let schema = message_store.schema();
+    let sql = format!(
+        r#"
+        SET search_path TO {schema}, public;
+
+        CREATE OR REPLACE FUNCTION message_store_version()
+        RETURNS varchar AS $$ SELECT '1.0.0'::varchar $$ LANGUAGE sql;
+
+        DROP FUNCTION get_last_stream_message(varchar, varchar);
+        CREATE FUNCTION get_last_stream_message(stream_name varchar)
+        RETURNS SETOF message AS $$ SELECT NULL::message WHERE false $$ LANGUAGE sql;
+
+        CREATE FUNCTION get_category_messages(
+          category varchar,
+          "position" bigint DEFAULT 1,
+          batch_size bigint DEFAULT 1000,
+          correlation varchar DEFAULT NULL,
+          condition varchar DEFAULT NULL
+        )
+        RETURNS SETOF message AS $$ SELECT NULL::message WHERE false $$ LANGUAGE sql;
+
+        DROP INDEX messages_category;
+        CREATE INDEX messages_category ON messages (category(stream_name), global_position);
+
+        RESET search_path;
+        "#
+    );
+    message_store.pool().execute(sql.as_str()).await.unwrap();
+}
+
+/// Returns the number of overloads of a server function.
+async fn overloads(message_store: &MessageStore, function: &str) -> i64 {
+    sqlx::query_scalar(
+        "SELECT COUNT(*) FROM pg_proc
+         WHERE pronamespace = $1::regnamespace AND proname = $2",
+    )
+    .bind(message_store.schema())
+    .bind(function)
+    .fetch_one(message_store.pool())
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "requires a Message DB server at DATABASE_URL"]
+async fn it_migrates_from_1_0_0() {
+    let message_store = message_store().await;
+    MessageStore::write_message(
+        &message_store,
+        "account-1",
+        "Deposited",
+        &json!({ "amount": 10 }),
+        &WriteMessageOpts::default(),
+    )
+    .await
+    .unwrap();
+    downgrade_to_1_0_0(&message_store).await;
+
+    message_store.migrate().await.unwrap();
+
+    let version: String = sqlx::query_scalar(&format!(
+        "SELECT {}.message_store_version()",
+        message_store.schema()
+    ))
+    .fetch_one(message_store.pool())
+    .await
+    .unwrap();
+    assert_eq!(version, "1.3.0");
+    assert_eq!(overloads(&message_store, "get_category_messages").await, 1);
+    assert_eq!(
+        overloads(&message_store, "get_last_stream_message").await,
+        1
+    );
+
+    let index: String =
+        sqlx::query_scalar("SELECT pg_get_indexdef(($1 || '.messages_category')::regclass)")
+            .bind(message_store.schema())
+            .fetch_one(message_store.pool())
+            .await
+            .unwrap();
+    assert!(index.contains("correlationStreamName"), "{index}");
+
+    let messages = MessageStore::get_category_messages::<MessageData, _>(
+        &message_store,
+        "account",
+        &GetCategoryMessagesOpts::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(messages.len(), 1);
+    let last =
+        MessageStore::get_last_stream_message::<MessageData, _>(&message_store, "account-1", None)
+            .await
+            .unwrap();
+    assert!(last.is_some());
+
+    drop_schema(message_store).await;
+}
@@ -0,0 +1,129 @@
+//! Retried writes against a Message DB server. See [`common`].
+
+mod common;
+
+use std::cell::Cell;
+
+use message_db::database::{MessageStore, RetryOpts, WriteMessageOpts};
+use message_db::message::{Message, MessageData, MessageType, StreamPosition};
+use serde::Serialize;
+use serde_json::json;
+use tokio::runtime::Handle;
+
+use crate::common::{drop_schema, message_store};
+
+#[derive(Serialize)]
+struct Withdrawn {
+    amount: i64,
+}
+
+impl MessageType for Withdrawn {
+    const MSG_TYPE: &'static str = "Withdrawn";
+}
+
+#[derive(Debug)]
+enum AccountError {
+    InsufficientFunds,
+    MessageStore(message_db::Error),
+}
+
+impl From<message_db::Error> for AccountError {
+    fn from(err: message_db::Error) -> Self {
+        AccountError::MessageStore(err)
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires a Message DB server at DATABASE_URL"]
+async fn it_returns_decide_errors_without_retrying() {
+    let message_store = message_store().await;
+    let attempts = Cell::new(0);
+
+    let result = message_store
+        .write_with_retry(
+            "account-1",
+            &RetryOpts::default(),
+            |_: &[Message<MessageData>]| {
+                attempts.set(attempts.get() + 1);
+                Err::<Vec<Withdrawn>, _>(AccountError::InsufficientFunds)
+            },
+        )
+        .await;
+
+    assert!(matches!(result, Err(AccountError::InsufficientFunds)));
+    assert_eq!(attempts.get(), 1);
+
+    drop_schema(message_store).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a Message DB server at DATABASE_URL"]
+async fn it_retries_conflicts_with_custom_decide_errors() {
+    let message_store = message_store().await;
+    let attempts = Cell::new(0);
+
+    let positions = message_store
+        .write_with_retry(
+            "account-1",
+            &RetryOpts::default(),
+            |messages: &[Message<MessageData>]| {
+                attempts.set(attempts.get() + 1);
+                if attempts.get() == 1 {
+                    // Writes to the stream after it was loaded, so that the
+                    // first attempt conflicts.
+                    tokio::task::block_in_place(|| {
+                        Handle::current().block_on(MessageStore::write_message(
+                            &message_store,
+                            "account-1",
+                            "Deposited",
+                            &json!({ "amount": 10 }),
+                            &WriteMessageOpts::default(),
+                        ))
+                    })?;
+                }
+                let amount = messages.len() as i64 * 10;
+                Ok::<_, AccountError>(vec![Withdrawn { amount }])
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(positions, vec![StreamPosition(1)]);
+    assert_eq!(attempts.get(), 2);
+
+    drop_schema(message_store).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a Message DB server at DATABASE_URL"]
+async fn it_converts_exhausted_retries_into_decide_errors() {
+    let message_store = message_store().await;
+
+    let result = message_store
+        .write_with_retry(
+            "account-1",
+            &RetryOpts::builder().max_attempts(1).build(),
+            |_: &[Message<MessageData>]| {
+                tokio::task::block_in_place(|| {
+                    Handle::current().block_on(MessageStore::write_message(
+                        &message_store,
+                        "account-1",
+                        "Deposited",
+                        &json!({ "amount": 10 }),
+                        &WriteMessageOpts::default(),
+                    ))
+                })?;
+                Ok::<_, AccountError>(vec![Withdrawn { amount: 10 }])
+            },
+        )
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(AccountError::MessageStore(
+            message_db::Error::RetriesExhausted { attempts: 1, .. }
+        ))
+    ));
+
+    drop_schema(message_store).await;
+}
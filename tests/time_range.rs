@@ -0,0 +1,156 @@
+//! Time range reads against a Message DB server. See [`common`].
+
+mod common;
+
+use std::ops::{Bound, RangeBounds};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use message_db::database::{MessageStore, WriteMessageOpts};
+use message_db::message::{GlobalPosition, Message, MessageData};
+use serde_json::json;
+
+use crate::common::{drop_schema, message_store};
+
+const STREAMS: &[&str] = &["account-1", "audit-1", "account-2", "account-1"];
+
+/// Writes a message to each of [`STREAMS`] at increasing times, returning the
+/// messages written.
+async fn write_messages(message_store: &MessageStore) -> Vec<Message<MessageData>> {
+    for stream_name in STREAMS {
+        MessageStore::write_message(
+            message_store,
+            stream_name,
+            "Written",
+            &json!({}),
+            &WriteMessageOpts::default(),
+        )
+        .await
+        .unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    MessageStore::read_all(message_store, &Default::default())
+        .try_collect()
+        .await
+        .unwrap()
+}
+
+async fn category_positions(
+    message_store: &MessageStore,
+    range: impl RangeBounds<DateTime<Utc>>,
+) -> Vec<i64> {
+    MessageStore::read_category_in_time_range::<MessageData, _>(
+        message_store,
+        "account",
+        range,
+        Some(1),
+    )
+    .map_ok(|message| message.global_position.0)
+    .try_collect()
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "requires a Message DB server at DATABASE_URL"]
+async fn it_finds_the_first_global_position_at_a_time() {
+    let message_store = message_store().await;
+    let messages = write_messages(&message_store).await;
+
+    for message in &messages {
+        let position = MessageStore::first_global_position_at(&message_store, message.time)
+            .await
+            .unwrap();
+        assert_eq!(position, Some(message.global_position));
+    }
+    let after_first = messages[0].time + chrono::Duration::microseconds(1);
+    assert_eq!(
+        MessageStore::first_global_position_at(&message_store, after_first)
+            .await
+            .unwrap(),
+        Some(messages[1].global_position)
+    );
+    let after_last = messages[3].time + chrono::Duration::microseconds(1);
+    assert_eq!(
+        MessageStore::first_global_position_at(&message_store, after_last)
+            .await
+            .unwrap(),
+        None::<GlobalPosition>
+    );
+
+    drop_schema(message_store).await;
+}
+
+#[tokio::test]
+#[ignore = "requires a Message DB server at DATABASE_URL"]
+async fn it_reads_categories_in_time_ranges() {
+    let message_store = message_store().await;
+    let messages = write_messages(&message_store).await;
+    let time = |index: usize| messages[index].time;
+    let position = |index: usize| messages[index].global_position.0;
+
+    assert_eq!(
+        category_positions(&message_store, time(0)..=time(2)).await,
+        vec![position(0), position(2)]
+    );
+    assert_eq!(
+        category_positions(&message_store, time(0)..time(2)).await,
+        vec![position(0)]
+    );
+    assert_eq!(
+        category_positions(
+            &message_store,
+            (Bound::Excluded(time(0)), Bound::Included(time(3)))
+        )
+        .await,
+        vec![position(2), position(3)]
+    );
+    assert_eq!(
+        category_positions(&message_store, time(1)..).await,
+        vec![position(2), position(3)]
+    );
+    assert_eq!(
+        category_positions(&message_store, ..time(3)).await,
+        vec![position(0), position(2)]
+    );
+
+    drop_schema(message_store).await;
+}
+
+#[tokio::test]
+#[ignore = "requires a Message DB server at DATABASE_URL"]
+async fn it_reads_streams_and_all_messages_in_time_ranges() {
+    let message_store = message_store().await;
+    let messages = write_messages(&message_store).await;
+    let time = |index: usize| messages[index].time;
+
+    let positions: Vec<i64> = MessageStore::read_stream_in_time_range::<MessageData, _>(
+        &message_store,
+        "account-1",
+        (Bound::Excluded(time(0)), Bound::Included(time(3))),
+        Some(1),
+    )
+    .map_ok(|message| message.position.0)
+    .try_collect()
+    .await
+    .unwrap();
+    assert_eq!(positions, vec![1]);
+
+    let positions: Vec<i64> = MessageStore::read_all_in_time_range::<MessageData, _>(
+        &message_store,
+        time(1)..time(3),
+        Some(1),
+    )
+    .map_ok(|message| message.global_position.0)
+    .try_collect()
+    .await
+    .unwrap();
+    assert_eq!(
+        positions,
+        vec![messages[1].global_position.0, messages[2].global_position.0]
+    );
+
+    drop_schema(message_store).await;
+}